@@ -0,0 +1,65 @@
+use crate::adjacency::Adjacency;
+
+// Slots whose unit may differ from what it was one generation ago. A unit whose
+// neighbourhood did not change can not change on the next tick either, so only
+// these slots and their neighbours need to be evaluated.
+pub struct ActiveSet {
+    changed: Vec<u32>,
+    seen: Vec<bool>,
+    everything: bool,
+}
+
+impl ActiveSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            changed: Vec::new(),
+            seen: vec![false; len],
+            everything: true,
+        }
+    }
+
+    pub fn touch(&mut self, slot: usize) {
+        if !self.everything {
+            self.changed.push(slot as u32);
+        }
+    }
+
+    pub fn touch_all(&mut self) {
+        self.everything = true;
+        self.changed.clear();
+    }
+
+    pub fn is_everything(&self) -> bool {
+        self.everything
+    }
+
+    pub fn record(&mut self, changed: impl Iterator<Item = u32>) {
+        self.everything = false;
+        self.changed.extend(changed);
+    }
+
+    // Drains the changed slots and returns them together with their neighbours,
+    // every slot at most once.
+    pub fn take_candidates(&mut self, adjacency: &Adjacency) -> Vec<u32> {
+        let mut candidates = Vec::with_capacity(self.changed.len() * 7);
+
+        std::mem::take(&mut self.changed)
+            .into_iter()
+            .for_each(|slot| {
+                std::iter::once(slot)
+                    .chain(adjacency.neighbours(slot as usize).iter().copied())
+                    .for_each(|slot| {
+                        if !self.seen[slot as usize] {
+                            self.seen[slot as usize] = true;
+                            candidates.push(slot);
+                        }
+                    })
+            });
+
+        candidates
+            .iter()
+            .for_each(|&slot| self.seen[slot as usize] = false);
+
+        candidates
+    }
+}
@@ -0,0 +1,172 @@
+use crate::game::{as_cartesian, slot_of};
+
+// Neighbour table in compressed sparse row form: the neighbours of slot `i`
+// are `neighbours[offsets[i]..offsets[i + 1]]`. Pentagons get 5 entries,
+// every other cell gets 6. Each row goes around its cell in a fixed rotational
+// direction, so consecutive entries are neighbours of each other as well.
+//
+// With a radius above 1 a row holds every other cell of `grid_disk(radius)`
+// instead, ring by ring, which is less than `3 * radius * (radius + 1)` cells
+// near pentagons. `distances` then holds the ring of every entry.
+pub struct Adjacency {
+    radius: u32,
+    offsets: Vec<u32>,
+    neighbours: Vec<u32>,
+    distances: Vec<u8>,
+}
+
+const FIRST_RING: [u8; 6] = [1; 6];
+
+impl Adjacency {
+    pub fn new(indecies: &[h3o::CellIndex]) -> Self {
+        Self::with_radius(indecies, 1)
+    }
+
+    pub fn with_radius(indecies: &[h3o::CellIndex], radius: u32) -> Self {
+        let mut offsets = Vec::with_capacity(indecies.len() + 1);
+        let mut neighbours = Vec::with_capacity(indecies.len() * 6);
+        let mut distances = Vec::new();
+
+        // Converting a cell to a position is slow, so every cell is converted once
+        // rather than once per ring it is in.
+        let positions: Vec<_> = match radius {
+            1 => indecies
+                .iter()
+                .map(|&index| as_cartesian(&h3o::LatLng::from(index)))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        offsets.push(0);
+        indecies.iter().enumerate().for_each(|(slot, index)| {
+            // Slots are in the order of the cells, so sorting by slot sorts by cell.
+            let mut disk = index
+                .grid_disk_distances::<Vec<_>>(radius)
+                .into_iter()
+                .filter(|(idx, _)| idx != index)
+                .map(|(idx, distance)| {
+                    let slot = slot_of(indecies, idx).expect("neighbour of a known cell");
+                    (slot as u32, distance)
+                })
+                .collect::<Vec<_>>();
+
+            if radius == 1 {
+                sort_around(&positions, slot, &mut disk);
+            } else {
+                disk.sort_unstable_by_key(|&(slot, distance)| (distance, slot));
+                distances.extend(disk.iter().map(|&(_, distance)| distance as u8));
+            }
+
+            neighbours.extend(disk.into_iter().map(|(slot, _)| slot));
+            offsets.push(neighbours.len() as u32);
+        });
+
+        Self {
+            radius,
+            offsets,
+            neighbours,
+            distances,
+        }
+    }
+
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    pub fn neighbours(&self, slot: usize) -> &[u32] {
+        &self.neighbours[self.offsets[slot] as usize..self.offsets[slot + 1] as usize]
+    }
+
+    // Ring of each entry of `neighbours(slot)`.
+    pub fn distances(&self, slot: usize) -> &[u8] {
+        let (from, to) = (self.offsets[slot] as usize, self.offsets[slot + 1] as usize);
+
+        if self.radius == 1 {
+            &FIRST_RING[..to - from]
+        } else {
+            &self.distances[from..to]
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Orders the slots of `ring` by the angle each cell makes around the cell of
+// `center` on the tangent plane.
+fn sort_around(positions: &[(f64, f64, f64)], center: usize, ring: &mut [(u32, u32)]) {
+    let sub =
+        |(x1, y1, z1): (f64, f64, f64), (x2, y2, z2): (f64, f64, f64)| (x1 - x2, y1 - y2, z1 - z2);
+    let dot =
+        |(x1, y1, z1): (f64, f64, f64), (x2, y2, z2): (f64, f64, f64)| x1 * x2 + y1 * y2 + z1 * z2;
+    let cross = |(x1, y1, z1): (f64, f64, f64), (x2, y2, z2): (f64, f64, f64)| {
+        (y1 * z2 - z1 * y2, z1 * x2 - x1 * z2, x1 * y2 - y1 * x2)
+    };
+
+    let Some(&(first, _)) = ring.first() else {
+        return;
+    };
+    let c = positions[center];
+    let u = sub(positions[first as usize], c);
+    let v = cross(c, u);
+
+    let mut angles: Vec<_> = ring
+        .iter()
+        .map(|&(slot, distance)| {
+            let d = sub(positions[slot as usize], c);
+            (dot(d, v).atan2(dot(d, u)), (slot, distance))
+        })
+        .collect();
+    angles.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    ring.iter_mut()
+        .zip(angles)
+        .for_each(|(entry, (_, sorted))| *entry = sorted);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(resolution: h3o::Resolution) -> Vec<h3o::CellIndex> {
+        let mut indecies = h3o::CellIndex::base_cells()
+            .flat_map(|index| index.children(resolution))
+            .collect::<Vec<_>>();
+        indecies.sort_unstable();
+        indecies
+    }
+
+    #[test]
+    fn rings_go_around_their_cell() {
+        let indecies = cells(h3o::Resolution::Two);
+        let adjacency = Adjacency::new(&indecies);
+
+        for slot in 0..adjacency.len() {
+            let ring = adjacency.neighbours(slot);
+            assert_eq!(ring.len(), if indecies[slot].is_pentagon() { 5 } else { 6 });
+
+            for (k, &neighbour) in ring.iter().enumerate() {
+                let next = indecies[ring[(k + 1) % ring.len()] as usize];
+                let distance = indecies[neighbour as usize].grid_distance(next);
+                assert_eq!(distance, Ok(1), "{}", indecies[slot]);
+            }
+        }
+    }
+
+    #[test]
+    fn wide_rows_go_ring_by_ring() {
+        let indecies = cells(h3o::Resolution::One);
+        let adjacency = Adjacency::with_radius(&indecies, 3);
+
+        for slot in 0..adjacency.len() {
+            let distances = adjacency.distances(slot);
+            assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(distances.iter().all(|&d| (1..=3).contains(&d)));
+        }
+    }
+}
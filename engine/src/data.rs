@@ -3,4 +3,11 @@ pub const ANOTHER_BACK_COLOR: [f32; 4] = [0.204, 0.286, 0.150, 1.0];
 pub const UNIT_COLOR: [f32; 4] = [0.1, 0.9, 0.1, 0.3];
 pub const GRASS_COLOR: [f32; 4] = [0.4, 0.9, 0.1, 1.0];
 pub const SCORCHD_COLOR: [f32; 4] = [0.9, 0.4, 0.1, 1.0];
+pub const PREVIEW_COLOR: [f32; 4] = [0.9, 0.8, 0.1, 0.6];
 pub const BORDER_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+pub const SPECIES_COLORS: [[f32; 4]; 4] = [
+    UNIT_COLOR,
+    [0.9, 0.1, 0.1, 0.3],
+    [0.1, 0.4, 0.9, 0.3],
+    [0.9, 0.8, 0.1, 0.3],
+];
@@ -0,0 +1,86 @@
+use crate::unit::UnitData;
+
+// A change to the units of a game made by hand rather than by a tick.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Edit<S = bool> {
+    // Brings every listed cell to life, e.g. the cells of a `Pattern`.
+    Stamp(Vec<h3o::CellIndex>),
+    SetCell(h3o::CellIndex, UnitData<S>),
+    // See `Game::kill_everything`.
+    Clear,
+    // See `Game::spawn_life`.
+    Fill,
+}
+
+// The units an edit changed, as they were before and after it.
+struct Applied<S> {
+    before: Vec<(u32, UnitData<S>)>,
+    after: Vec<(u32, UnitData<S>)>,
+}
+
+// Edits that can be undone, newest last, and undone edits that can be redone.
+// Undoing an edit after some ticks writes back only the units it changed, as
+// they were before it, whatever the ticks since made of them.
+pub struct EditLog<S = bool> {
+    done: Vec<Applied<S>>,
+    undone: Vec<Applied<S>>,
+    capacity: usize,
+}
+
+impl<S: PartialEq + Copy> EditLog<S> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            done: Vec::new(),
+            undone: Vec::new(),
+            capacity,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    // Records an edit that turned the units `before` into `after`, both listing
+    // the same slots. Nothing is recorded when it changed no unit, and edits
+    // undone before can no longer be redone.
+    pub fn record(&mut self, before: &[(u32, UnitData<S>)], after: &[(u32, UnitData<S>)]) {
+        let (before, after): (Vec<_>, Vec<_>) = before
+            .iter()
+            .zip(after.iter())
+            .filter(|((_, before), (_, after))| before != after)
+            .unzip();
+
+        if before.is_empty() || self.capacity == 0 {
+            return;
+        }
+
+        if self.done.len() == self.capacity {
+            self.done.remove(0);
+        }
+        self.done.push(Applied { before, after });
+        self.undone.clear();
+    }
+
+    // The units to write back to undo the newest edit.
+    pub fn undo(&mut self) -> Option<&[(u32, UnitData<S>)]> {
+        let applied = self.done.pop()?;
+        self.undone.push(applied);
+        self.undone.last().map(|e| e.before.as_slice())
+    }
+
+    // The units to write back to redo the newest undone edit.
+    pub fn redo(&mut self) -> Option<&[(u32, UnitData<S>)]> {
+        let applied = self.undone.pop()?;
+        self.done.push(applied);
+        self.done.last().map(|e| e.after.as_slice())
+    }
+}
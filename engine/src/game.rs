@@ -1,27 +1,110 @@
-use std::collections::HashMap;
-
 use h3o::{LatLng, Resolution};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    active::ActiveSet,
+    adjacency::Adjacency,
+    edit::{Edit, EditLog},
+    history::History,
+    kernel::{BitKernel, CellKernel, Kernel},
+    rules::Rule,
+    unit::{CellState, UnitData},
+};
+
+// Units are stored densely, one per slot; slot `i` holds the unit of `indecies[i]`.
+pub struct Field<S = bool>(pub Vec<UnitData<S>>);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stepping {
+    // Evaluate every unit on every tick.
+    Full,
+    // Evaluate only the units that changed on the last tick and their neighbours.
+    Incremental,
+}
 
-use crate::{rules::SimpleRules, unit::UnitData};
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    // Run full ticks through `Unit::transform`.
+    Cells,
+    // Run full ticks through the bit-packed `BitKernel` whenever the state is
+    // binary and the rule is a `SimpleRules`, through `Unit::transform` otherwise.
+    Bits,
+}
+
+// Which field the bits of the `BitKernel` hold, so that full ticks in a row
+// step them on without loading them from `present` every time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Resident {
+    Nothing,
+    Present,
+    Future,
+}
 
-pub struct Field(pub HashMap<SphericalIndex, UnitData>);
-pub struct Game {
-    pub present: Field,
-    pub future: Field,
+pub struct Game<S = bool> {
+    pub present: Field<S>,
+    pub future: Field<S>,
 
     pub indecies: Vec<h3o::CellIndex>,
+    pub adjacency: Adjacency,
+    // Neighbourhoods of radius above 1, built the first time a rule asks for them.
+    wide: Vec<Adjacency>,
     pub resolution: h3o::Resolution,
+
+    pub stepping: Stepping,
+    pub backend: Backend,
+    // Share of units `spawn_life` brings to life, from 0 to 1.
+    pub density: f64,
+    active: ActiveSet,
+    bits: Option<BitKernel>,
+    // Like the active set, only sees changes made through the methods of the game.
+    resident: Resident,
+
+    // Every random draw of a game comes from `rng`, so a run is reproducible from `seed`.
+    seed: u64,
+    rng: StdRng,
+
+    // Ticks since the game was created, see `Game::step_back`.
+    generation: u64,
+    // Generation each unit last came to life in, only meaningful while it is alive.
+    born: Vec<u64>,
+    history: History<S>,
+    edits: EditLog<S>,
 }
 
-impl Game {
-    pub fn new(&resolution: &h3o::Resolution) -> Self {
+const HISTORY_LEN: usize = 100;
+const EDIT_LOG_LEN: usize = 100;
+
+impl<S: CellState> Game<S> {
+    pub fn new(resolution: &h3o::Resolution) -> Self {
+        Self::with_seed(resolution, rand::random())
+    }
+
+    pub fn with_seed(&resolution: &h3o::Resolution, seed: u64) -> Self {
+        let mut indecies = h3o::CellIndex::base_cells()
+            .flat_map(|index| index.children(resolution))
+            .collect::<Vec<_>>();
+        // Sorted so that slots can be looked up with a binary search.
+        indecies.sort_unstable();
+
         Self {
-            present: Field(std::collections::HashMap::<SphericalIndex, UnitData>::new()),
-            future: Field(std::collections::HashMap::<SphericalIndex, UnitData>::new()),
-            indecies: h3o::CellIndex::base_cells()
-                .flat_map(|index| index.children(resolution))
-                .collect::<Vec<_>>(),
+            present: Field(vec![UnitData::empty(); indecies.len()]),
+            future: Field(vec![UnitData::empty(); indecies.len()]),
+            adjacency: Adjacency::new(&indecies),
+            wide: Vec::new(),
+            active: ActiveSet::new(indecies.len()),
+            born: vec![0; indecies.len()],
+            indecies,
             resolution,
+            stepping: Stepping::Full,
+            backend: Backend::Bits,
+            density: 0.5,
+            bits: None,
+            resident: Resident::Nothing,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            generation: 0,
+            history: History::new(HISTORY_LEN),
+            edits: EditLog::new(EDIT_LOG_LEN),
         }
     }
 
@@ -30,51 +113,303 @@ impl Game {
         self
     }
 
+    pub fn len(&self) -> usize {
+        self.indecies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indecies.is_empty()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Restarts the random draws of the game from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    // For random draws made on behalf of the game, so that they replay from the seed too.
+    #[cfg(feature = "import")]
+    pub(crate) fn rng_mut(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    // For games restored from a save, see `save::load`.
+    pub(crate) fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    // How many past generations `step_back` can go back to.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn history_capacity(&self) -> usize {
+        self.history.capacity()
+    }
+
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history.set_capacity(capacity);
+    }
+
+    // Goes back `n` generations, or as many as the history holds, and returns
+    // how many it went back. Edits made in between are undone on the way, so
+    // ticks after that replay the same generations, and undo and redo start over.
+    pub fn step_back(&mut self, n: usize) -> usize {
+        let mut steps = 0;
+
+        // Edits are only popped on the way to the tick before them.
+        while steps < n && !self.history.is_empty() {
+            let Some(entry) = self.history.pop() else {
+                break;
+            };
+            entry
+                .changed
+                .into_iter()
+                .zip(entry.born)
+                .for_each(|((slot, unit), born)| {
+                    self.present.0[slot as usize] = unit;
+                    self.born[slot as usize] = born;
+                });
+            match entry.rng {
+                Some(rng) => {
+                    self.rng = rng;
+                    self.generation -= 1;
+                    steps += 1;
+                }
+                // The log can not tell which of its edits this was, and undoing
+                // any of them now would write units of a later generation.
+                None => self.edits.clear(),
+            }
+        }
+
+        if steps > 0 {
+            self.active.touch_all();
+            self.resident = Resident::Nothing;
+        }
+        steps
+    }
+
+    // Applies `edit` so that it can be undone, see `EditLog`.
+    pub fn edit(&mut self, edit: Edit<S>) {
+        // Only the units the edit can change are kept, a click should not copy the field.
+        let mut slots: Vec<u32> = match &edit {
+            Edit::Stamp(cells) => cells
+                .iter()
+                .filter_map(|&index| self.slot_of(index))
+                .map(|slot| slot as u32)
+                .collect(),
+            Edit::SetCell(index, _) => self
+                .slot_of(*index)
+                .map(|slot| slot as u32)
+                .into_iter()
+                .collect(),
+            Edit::Clear | Edit::Fill => (0..self.len() as u32).collect(),
+        };
+        slots.sort_unstable();
+        slots.dedup();
+        let before = self.units_at(&slots);
+        let born = self.born_at(&slots);
+        let replaces_field = matches!(edit, Edit::Clear | Edit::Fill);
+
+        match edit {
+            Edit::Stamp(_) => slots
+                .iter()
+                .for_each(|&slot| self.unit_mut(slot as usize).add_life()),
+            Edit::SetCell(_, unit) => slots
+                .iter()
+                .for_each(|&slot| *self.unit_mut(slot as usize) = unit),
+            // Both forget the history instead, see `forget_history`.
+            Edit::Clear => self.kill_everything(),
+            Edit::Fill => self.spawn_life(),
+        }
+
+        let after = self.units_at(&slots);
+        if !replaces_field {
+            self.history.record_edit(&before, &after, &born);
+        }
+        self.edits.record(&before, &after);
+    }
+
+    fn units_at(&self, slots: &[u32]) -> Vec<(u32, UnitData<S>)> {
+        slots
+            .iter()
+            .map(|&slot| (slot, self.present.0[slot as usize]))
+            .collect()
+    }
+
+    fn born_at(&self, slots: &[u32]) -> Vec<u64> {
+        slots.iter().map(|&slot| self.born[slot as usize]).collect()
+    }
+
+    // Writes back the units of an undo or redo, which the history keeps as an edit.
+    fn write_edit(&mut self, units: &[(u32, UnitData<S>)]) {
+        let slots: Vec<_> = units.iter().map(|&(slot, _)| slot).collect();
+        let (before, born) = (self.units_at(&slots), self.born_at(&slots));
+
+        write_units(
+            &mut self.present,
+            &mut self.born,
+            self.generation,
+            &mut self.active,
+            units,
+        );
+        self.resident = Resident::Nothing;
+        self.history.record_edit(&before, units, &born);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.edits.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.edits.can_redo()
+    }
+
+    // Returns whether there was an edit to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(units) = self.edits.undo().map(<[_]>::to_vec) else {
+            return false;
+        };
+        self.write_edit(&units);
+        true
+    }
+
+    // Returns whether there was an undone edit to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(units) = self.edits.redo().map(<[_]>::to_vec) else {
+            return false;
+        };
+        self.write_edit(&units);
+        true
+    }
+
+    // Generations the unit of `slot` has been alive for, `None` when it is not alive.
+    pub fn age(&self, slot: usize) -> Option<u64> {
+        self.present
+            .0
+            .get(slot)
+            .filter(|unit| unit.is_alive())
+            .map(|_| self.generation - self.born[slot])
+    }
+
+    pub fn slot_of(&self, index: h3o::CellIndex) -> Option<usize> {
+        slot_of(&self.indecies, index)
+    }
+
+    pub fn index_of(&self, slot: usize) -> Option<h3o::CellIndex> {
+        self.indecies.get(slot).copied()
+    }
+
+    pub fn neighbours(&self, index: h3o::CellIndex) -> impl Iterator<Item = h3o::CellIndex> + '_ {
+        self.slot_of(index)
+            .map(|slot| self.adjacency.neighbours(slot))
+            .unwrap_or_default()
+            .iter()
+            .map(|&slot| self.indecies[slot as usize])
+    }
+
+    // Brings a `density` share of units to life. The same seed and density give
+    // the same soup, see `Game::with_seed`.
     pub fn spawn_life(&mut self) {
-        self.indecies.iter().for_each(|index| {
-            let mut unit = UnitData::new();
-            unit.randomize_life(0.5);
+        self.spawn_life_with(|_| S::alive());
+    }
 
-            self.present.0.insert(SphericalIndex(*index), unit);
-            self.future.0.insert(SphericalIndex(*index), unit);
+    // Like `spawn_life`, but every unit brought to life gets the state `alive` draws.
+    pub fn spawn_life_with(&mut self, mut alive: impl FnMut(&mut StdRng) -> S) {
+        let (density, rng) = (self.density, &mut self.rng);
+        self.present.0.iter_mut().for_each(|unit| {
+            *unit = UnitData::new();
+            unit.randomize_life(density, rng);
+            if unit.is_alive() {
+                unit.state = alive(rng);
+            }
         });
+        self.future.0.copy_from_slice(&self.present.0);
+        self.born.fill(self.generation);
+        self.active.touch_all();
+        self.resident = Resident::Nothing;
+        self.forget_history();
     }
 
     pub fn kill_everything(&mut self) {
-        self.indecies.iter().for_each(|index| {
-            let unit = self.present.0.get_mut(&SphericalIndex(*index)).unwrap();
+        self.present.0.iter_mut().for_each(|unit| {
             *unit = unit.with_removed_life();
         });
+        self.active.touch_all();
+        self.resident = Resident::Nothing;
+        self.forget_history();
+    }
+
+    // Past ticks do not lead to a field replaced as a whole, or changed without an
+    // `edit`, so there is no stepping back over it.
+    fn forget_history(&mut self) {
+        self.history.clear();
     }
 
     pub fn remove_marks(&mut self) {
-        self.present.0.iter_mut().for_each(|(_k, v)| {
+        self.present.0.iter_mut().for_each(|v| {
             v.unmark();
         });
+        self.active.touch_all();
+        self.forget_history();
+    }
+
+    // Forces the next incremental tick to evaluate every unit, e.g. after the rules change.
+    pub fn touch_all(&mut self) {
+        self.active.touch_all();
     }
 
     pub fn unmark_unit(&mut self, index: h3o::CellIndex) {
-        self.present
-            .0
-            .get_mut(&SphericalIndex(index))
-            .iter_mut()
-            .for_each(|u| u.unmark());
+        if let Some(&unit) = self.get_unit(index) {
+            self.edit(Edit::SetCell(index, unit.with_removed_marked()));
+        }
     }
 
     pub fn mark_unit(&mut self, index: h3o::CellIndex) {
-        self.present
-            .0
-            .get_mut(&SphericalIndex(index))
-            .iter_mut()
-            .for_each(|u| u.mark());
+        if let Some(&unit) = self.get_unit(index) {
+            self.edit(Edit::SetCell(index, unit.with_mark()));
+        }
+    }
+
+    // Changes made through the returned unit bypass the history, so it is
+    // forgotten; `edit` keeps them.
+    pub fn get_mut_unit(&mut self, index: &h3o::CellIndex) -> Option<&mut UnitData<S>> {
+        let slot = self.slot_of(*index)?;
+        self.forget_history();
+        Some(self.unit_mut(slot))
+    }
+
+    fn unit_mut(&mut self, slot: usize) -> &mut UnitData<S> {
+        self.active.touch(slot);
+        self.resident = Resident::Nothing;
+        // Whatever the caller does, a unit not alive yet can only come to life now.
+        if !self.present.0[slot].is_alive() {
+            self.born[slot] = self.generation;
+        }
+        &mut self.present.0[slot]
     }
 
-    pub fn get_mut_unit(&mut self, index: &h3o::CellIndex) -> Option<&mut UnitData> {
-        self.present.0.get_mut(&SphericalIndex(*index))
+    pub fn get_unit(&self, index: h3o::CellIndex) -> Option<&UnitData<S>> {
+        let slot = self.slot_of(index)?;
+        self.present.0.get(slot)
     }
 
-    pub fn get_unit(&self, index: h3o::CellIndex) -> Option<&UnitData> {
-        self.present.0.get(&SphericalIndex(index))
+    // Cells that are both alive and marked, e.g. to capture them as a pattern.
+    pub fn marked_life(&self) -> Vec<h3o::CellIndex> {
+        self.indecies
+            .iter()
+            .zip(self.present.0.iter())
+            .filter(|(_, unit)| unit.marked && unit.is_alive())
+            .map(|(&index, _)| index)
+            .collect()
     }
 
     pub fn get_raw_coords(&self, index: h3o::CellIndex) -> (f64, f64) {
@@ -82,28 +417,105 @@ impl Game {
         (sph.lat_radians(), sph.lng_radians())
     }
 
-    pub fn next_tick(&mut self, rules: &SimpleRules) {
-        self.present
-            .0
-            .iter()
-            .map(|(idx, data)| {
-                (
-                    Unit {
-                        backref: &self.present,
-                        idx: *idx,
-                        data: *data,
-                    }
-                    .transform(rules),
-                    *idx,
-                )
+    pub fn next_tick<R: Rule<S> + ?Sized>(&mut self, rules: &R) {
+        let radius = rules.radius();
+        if radius > 1 && !self.wide.iter().any(|a| a.radius() == radius) {
+            self.wide
+                .push(Adjacency::with_radius(&self.indecies, radius));
+        }
+
+        // A rule that draws random numbers may change any unit, not only the candidates.
+        let stepping = match rules.is_deterministic() {
+            true => self.stepping,
+            false => Stepping::Full,
+        };
+        let rng = self.rng.clone();
+        let seed = self.rng.gen();
+
+        match stepping {
+            Stepping::Full => {
+                self.full_tick(rules, seed);
+                self.active.touch_all();
+            }
+            Stepping::Incremental if self.active.is_everything() => {
+                self.full_tick(rules, seed);
+
+                let (present, future) = (&self.present.0, &self.future.0);
+                self.active.record(
+                    (0..present.len())
+                        .filter(|&slot| present[slot] != future[slot])
+                        .map(|slot| slot as u32),
+                );
+            }
+            Stepping::Incremental => self.incremental_tick(rules),
+        }
+
+        self.history
+            .record(&self.present, &self.future, &self.born, rng);
+
+        let (present, future) = (&self.present.0, &self.future.0);
+        (0..present.len())
+            .filter(|&slot| future[slot].is_alive() && !present[slot].is_alive())
+            .for_each(|slot| self.born[slot] = self.generation + 1);
+        self.generation += 1;
+    }
+
+    fn full_tick<R: Rule<S> + ?Sized>(&mut self, rules: &R, seed: u64) {
+        let adjacency = neighbourhood(&self.adjacency, &self.wide, rules.radius());
+
+        match (self.backend, rules.as_simple().filter(|_| S::BINARY)) {
+            (Backend::Bits, Some(simple)) => {
+                let bits = self
+                    .bits
+                    .get_or_insert_with(|| BitKernel::new(&self.adjacency));
+                if self.resident != Resident::Present {
+                    bits.load(&self.present);
+                }
+                bits.step(simple);
+                bits.store(&self.present, &mut self.future);
+                self.resident = Resident::Future;
+            }
+            _ => {
+                CellKernel.tick(
+                    &self.indecies,
+                    adjacency,
+                    &self.present,
+                    &mut self.future,
+                    rules,
+                    seed,
+                );
+                self.resident = Resident::Nothing;
+            }
+        }
+    }
+
+    // `future` still holds the previous generation. Units outside of the candidates
+    // did not change then and can not change now, so their `future` is already right.
+    fn incremental_tick<R: Rule<S> + ?Sized>(&mut self, rules: &R) {
+        let adjacency = neighbourhood(&self.adjacency, &self.wide, rules.radius());
+        let candidates = self.active.take_candidates(adjacency);
+
+        let changed = candidates
+            .into_iter()
+            .filter(|&slot| {
+                let slot = slot as usize;
+                let unit =
+                    Unit::new(&self.present, adjacency, &self.indecies, slot).transform(rules);
+                self.future.0[slot] = unit;
+                unit != self.present.0[slot]
             })
-            .for_each(|(u, idx)| {
-                self.future.0.insert(idx, u);
-            });
+            .collect::<Vec<_>>();
+
+        self.active.record(changed.into_iter());
+        self.resident = Resident::Nothing;
     }
 
     pub fn swap_buffers(&mut self) {
         std::mem::swap(&mut self.present, &mut self.future);
+        self.resident = match self.resident {
+            Resident::Future => Resident::Present,
+            _ => Resident::Nothing,
+        };
     }
 
     pub fn cell_to_colored_face_vtxes(
@@ -140,15 +552,26 @@ impl Game {
 
     pub fn decrease_fineness(&mut self) {
         if let Some(resolution) = dec_resolution(&self.resolution) {
-            *self = Game::new(&resolution).with_spawned_life();
+            self.reset_with_resolution(resolution);
         }
     }
 
     pub fn increase_fineness(&mut self) {
         if let Some(resolution) = inc_resolution(&self.resolution) {
-            *self = Game::new(&resolution).with_spawned_life();
+            self.reset_with_resolution(resolution);
         }
     }
+
+    fn reset_with_resolution(&mut self, resolution: h3o::Resolution) {
+        let (stepping, backend, density) = (self.stepping, self.backend, self.density);
+        let history = self.history_capacity();
+        *self = Game::with_seed(&resolution, self.seed);
+        self.stepping = stepping;
+        self.backend = backend;
+        self.density = density;
+        self.set_history_capacity(history);
+        self.spawn_life();
+    }
 }
 
 pub fn as_number(r: &h3o::Resolution) -> u32 {
@@ -206,39 +629,117 @@ fn dec_resolution(r: &h3o::Resolution) -> Option<h3o::Resolution> {
     }
 }
 
-impl Default for Game {
+impl<S: CellState> Default for Game<S> {
     fn default() -> Self {
         Self::new(&h3o::Resolution::Two)
     }
 }
 
-pub struct Unit<'a> {
-    backref: &'a Field,
+pub struct Unit<'a, S = bool> {
+    backref: &'a Field<S>,
+    neighbours: &'a [u32],
+    distances: &'a [u8],
+    pub slot: usize,
     pub idx: SphericalIndex,
-    pub data: UnitData,
+    pub data: UnitData<S>,
+    seed: u64,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub struct SphericalIndex(pub h3o::CellIndex);
 
-impl<'a> Unit<'a> {
-    pub fn get_neighbours(&self) -> impl Iterator<Item = UnitData> + '_ {
-        self.idx
-            .0
-            .grid_disk::<Vec<_>>(1)
-            .into_iter()
-            .filter(|idx| *idx != self.idx.0)
-            .map(|idx| *self.backref.0.get(&SphericalIndex(idx)).unwrap())
+impl<'a, S: CellState> Unit<'a, S> {
+    pub fn new(
+        backref: &'a Field<S>,
+        adjacency: &'a Adjacency,
+        indecies: &[h3o::CellIndex],
+        slot: usize,
+    ) -> Self {
+        Self {
+            backref,
+            neighbours: adjacency.neighbours(slot),
+            distances: adjacency.distances(slot),
+            slot,
+            idx: SphericalIndex(indecies[slot]),
+            data: backref.0[slot],
+            seed: 0,
+        }
     }
 
-    pub fn transform(&self, rules: &SimpleRules) -> UnitData {
-        let n: usize = self.get_neighbours().filter(|n| n.inhabited).count();
+    // Seed of the tick the unit is evaluated in, see `Unit::rng`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
 
-        self.data
-            .with_set_life(rules.apply(n, self.data.inhabited).unwrap())
+    // Random numbers for this unit on this tick. They only depend on the tick
+    // seed and the slot, so units can be evaluated in any order or in parallel.
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed ^ (self.slot as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    pub fn get_neighbours(&self) -> impl Iterator<Item = UnitData<S>> + '_ {
+        self.neighbours
+            .iter()
+            .map(|&slot| self.backref.0[slot as usize])
+    }
+
+    // Every neighbour together with the ring it lies on, see `Rule::radius`.
+    pub fn get_neighbours_with_distance(&self) -> impl Iterator<Item = (UnitData<S>, u32)> + '_ {
+        self.get_neighbours()
+            .zip(self.distances.iter().map(|&d| d as u32))
+    }
+
+    pub fn is_pentagon(&self) -> bool {
+        self.idx.0.is_pentagon()
+    }
+
+    // Number of cells the neighbourhood has, the unit itself not included.
+    pub fn neighbourhood_size(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    pub fn alive_neighbours(&self) -> usize {
+        self.get_neighbours().filter(|n| n.is_alive()).count()
+    }
+
+    pub fn transform<R: Rule<S> + ?Sized>(&self, rules: &R) -> UnitData<S> {
+        self.data.with_state(rules.next_state(self))
     }
 }
 
+// Units brought back to life by an undo or redo count as born again.
+fn write_units<S: CellState>(
+    field: &mut Field<S>,
+    born: &mut [u64],
+    generation: u64,
+    active: &mut ActiveSet,
+    units: &[(u32, UnitData<S>)],
+) {
+    units.iter().for_each(|&(slot, unit)| {
+        let slot = slot as usize;
+        if unit.is_alive() && !field.0[slot].is_alive() {
+            born[slot] = generation;
+        }
+        field.0[slot] = unit;
+        active.touch(slot);
+    });
+}
+
+fn neighbourhood<'a>(
+    adjacency: &'a Adjacency,
+    wide: &'a [Adjacency],
+    radius: u32,
+) -> &'a Adjacency {
+    wide.iter()
+        .find(|a| a.radius() == radius)
+        .unwrap_or(adjacency)
+}
+
+pub(crate) fn slot_of(indecies: &[h3o::CellIndex], index: h3o::CellIndex) -> Option<usize> {
+    indecies.binary_search(&index).ok()
+}
+
 pub fn as_cartesian(ltln: &h3o::LatLng) -> (f64, f64, f64) {
     let (lt, ln) = (ltln.lat_radians(), ltln.lng_radians());
     (lt.cos() * ln.cos(), lt.cos() * ln.sin(), lt.sin())
@@ -249,3 +750,128 @@ pub fn as_spherical((x, y, z): &(f64, f64, f64)) -> Option<h3o::LatLng> {
     let lng = y.atan2(*x);
     h3o::LatLng::from_radians(lat, lng).ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{PentagonPolicy, SimpleRules};
+
+    const RULES: [&str; 5] = ["B2/S34", "B2/S35", "B3/S23", "B24/S356", "B13/S0246"];
+
+    const MODES: [(Stepping, Backend); 4] = [
+        (Stepping::Full, Backend::Cells),
+        (Stepping::Full, Backend::Bits),
+        (Stepping::Incremental, Backend::Cells),
+        (Stepping::Incremental, Backend::Bits),
+    ];
+
+    fn policies() -> [PentagonPolicy; 3] {
+        [
+            PentagonPolicy::SameTable,
+            PentagonPolicy::Separate {
+                survives: [false, false, true, true, false, false, false],
+                emerges: [false, true, false, true, false, false, false],
+            },
+            PentagonPolicy::Rescale,
+        ]
+    }
+
+    // The live cells after every tick of a run with edits, undos and steps back in it.
+    fn run(stepping: Stepping, backend: Backend, rules: &SimpleRules) -> Vec<Vec<bool>> {
+        let mut game: Game = Game::with_seed(&Resolution::Two, 1).with_spawned_life();
+        game.stepping = stepping;
+        game.backend = backend;
+
+        let mut generations = Vec::new();
+        for tick in 0..40 {
+            match tick {
+                10 => game.edit(Edit::Stamp(game.indecies[..50].to_vec())),
+                15 => assert!(game.undo()),
+                20 => {
+                    let (index, unit) = (game.indecies[7], game.present.0[7]);
+                    game.edit(Edit::SetCell(index, unit.with_set_life(!unit.is_alive())));
+                }
+                25 => assert_eq!(game.step_back(3), 3),
+                30 => assert!(game.undo()),
+                33 => assert!(game.redo()),
+                _ => (),
+            }
+
+            game.next_tick(rules);
+            game.swap_buffers();
+            generations.push(game.present.0.iter().map(|unit| unit.is_alive()).collect());
+        }
+        generations
+    }
+
+    #[test]
+    fn backends_and_steppings_agree() {
+        for rule in RULES {
+            for pentagons in policies() {
+                let rules = SimpleRules {
+                    pentagons,
+                    ..rule.parse().unwrap()
+                };
+                let (reference, others) = MODES.split_first().unwrap();
+                let expected = run(reference.0, reference.1, &rules);
+
+                for &(stepping, backend) in others {
+                    let generations = run(stepping, backend, &rules);
+                    let differs = (0..expected.len()).find(|&n| generations[n] != expected[n]);
+                    assert_eq!(differs, None, "{rules:?} {stepping:?} {backend:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stepping_back_over_an_edit_forgets_the_edit_log() {
+        let rules = SimpleRules::default();
+        let mut game: Game = Game::with_seed(&Resolution::Two, 1).with_spawned_life();
+        let tick = |game: &mut Game| {
+            game.next_tick(&rules);
+            game.swap_buffers();
+        };
+
+        (0..3).for_each(|_| tick(&mut game));
+        let before: Vec<_> = game.present.0.clone();
+        game.edit(Edit::Stamp(game.indecies[..200].to_vec()));
+        (0..2).for_each(|_| tick(&mut game));
+
+        assert_eq!(game.step_back(2), 2);
+        assert!(game.present.0 != before);
+        assert!(game.can_undo());
+
+        assert_eq!(game.step_back(1), 1);
+        assert_eq!(game.generation(), 2);
+        assert!(!game.can_undo() && !game.can_redo());
+        assert!(!game.undo());
+
+        // Generation 3 again, without the stamp.
+        tick(&mut game);
+        assert!(game.present.0 == before);
+    }
+
+    #[test]
+    fn edits_do_not_count_against_the_history_capacity() {
+        let rules = SimpleRules::default();
+        let mut game: Game = Game::with_seed(&Resolution::Two, 1).with_spawned_life();
+        game.set_history_capacity(10);
+        let tick = |game: &mut Game| {
+            game.next_tick(&rules);
+            game.swap_buffers();
+        };
+
+        (0..10).for_each(|_| tick(&mut game));
+        for slot in 0..10 {
+            let index = game.indecies[slot];
+            game.mark_unit(index);
+        }
+        assert_eq!(game.history_len(), 10);
+
+        tick(&mut game);
+        assert_eq!(game.history_len(), 10);
+        assert_eq!(game.step_back(10), 10);
+        assert!(game.present.0.iter().all(|unit| !unit.marked));
+    }
+}
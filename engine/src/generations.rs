@@ -0,0 +1,188 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    data,
+    game::Unit,
+    rules::{format_counts, parse_survives_emerges, ParseRulesError, Rule},
+    unit::{lerp_colors, CellState},
+};
+
+// State of a cell under "Generations" rules: a cell that fails to survive does
+// not die at once but goes through `of` refractory states, during which it is
+// neither alive nor able to give birth.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Generation {
+    #[default]
+    Empty,
+    Alive,
+    Dying {
+        left: u8,
+        of: u8,
+    },
+}
+
+impl CellState for Generation {
+    fn alive() -> Self {
+        Generation::Alive
+    }
+
+    fn is_alive(&self) -> bool {
+        *self == Generation::Alive
+    }
+
+    fn color(&self, is_penta: bool) -> [f32; 4] {
+        match *self {
+            Generation::Empty => false.color(is_penta),
+            Generation::Alive => true.color(is_penta),
+            Generation::Dying { left, of } => lerp_colors(
+                &false.color(is_penta),
+                &data::SCORCHD_COLOR,
+                left as f32 / (of as f32 + 1.0),
+            ),
+        }
+    }
+}
+
+// `B2/S345/C4`: birth and survival counts plus the total number of states,
+// empty and alive included. `C2` behaves like `SimpleRules`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GenerationsRules {
+    pub survives: [bool; 7],
+    pub emerges: [bool; 7],
+    pub states: u8,
+}
+
+impl GenerationsRules {
+    pub fn refractory_states(&self) -> u8 {
+        self.states.saturating_sub(2)
+    }
+}
+
+impl Rule<Generation> for GenerationsRules {
+    fn next_state(&self, unit: &Unit<Generation>) -> Generation {
+        let n = unit.alive_neighbours();
+
+        match unit.data.state {
+            Generation::Empty if self.emerges.get(n) == Some(&true) => Generation::Alive,
+            Generation::Empty => Generation::Empty,
+            Generation::Alive if self.survives.get(n) == Some(&true) => Generation::Alive,
+            Generation::Alive => match self.refractory_states() {
+                0 => Generation::Empty,
+                of => Generation::Dying { left: of, of },
+            },
+            Generation::Dying { left: 1, .. } => Generation::Empty,
+            Generation::Dying { left, of } => Generation::Dying { left: left - 1, of },
+        }
+    }
+}
+
+impl Default for GenerationsRules {
+    // Brian's brain like: `B2/S/C3`.
+    fn default() -> Self {
+        let mut emerges: [bool; 7] = Default::default();
+
+        emerges[2] = true;
+
+        Self {
+            survives: Default::default(),
+            emerges,
+            states: 3,
+        }
+    }
+}
+
+// Accepts `B2/S345/C4` with the parts in any order, and the survive/birth/states form `345/2/4`.
+impl FromStr for GenerationsRules {
+    type Err = ParseRulesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.trim().split('/').map(str::trim).collect::<Vec<_>>();
+        let [first, second, third] = parts[..] else {
+            return Err(match parts.len() {
+                2 => ParseRulesError::MissingPart('C'),
+                _ => ParseRulesError::MissingSeparator,
+            });
+        };
+
+        let is_states = |part: &&str| part.starts_with(['C', 'c']);
+        let (counts, states) = match [first, second, third].iter().position(is_states) {
+            Some(0) => ([second, third], &first[1..]),
+            Some(1) => ([first, third], &second[1..]),
+            Some(_) => ([first, second], &third[1..]),
+            None => ([first, second], third),
+        };
+
+        let (survives, emerges) = parse_survives_emerges(counts[0], counts[1])?;
+        let states = states
+            .trim()
+            .parse::<u8>()
+            .ok()
+            .filter(|&states| states >= 2)
+            .ok_or_else(|| ParseRulesError::InvalidStates(states.to_string()))?;
+
+        Ok(Self {
+            survives,
+            emerges,
+            states,
+        })
+    }
+}
+
+impl fmt::Display for GenerationsRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "B{}/S{}/C{}",
+            format_counts(&self.emerges),
+            format_counts(&self.survives),
+            self.states
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_notation() {
+        for rule in ["B2/S/C3", "C3/S/B2", "s/b2/c3", "/2/3"] {
+            assert_eq!(
+                rule.parse::<GenerationsRules>(),
+                Ok(GenerationsRules::default()),
+                "{rule}"
+            );
+        }
+    }
+
+    #[test]
+    fn formats_what_it_parses() {
+        for rule in ["B2/S/C3", "B2/S345/C4", "B0123456/S0123456/C255"] {
+            let rules = rule.parse::<GenerationsRules>().unwrap();
+            assert_eq!(rules.to_string(), rule);
+            assert_eq!(rules.to_string().parse(), Ok(rules));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let cases = [
+            ("B2", ParseRulesError::MissingSeparator),
+            ("B2/S/C3/C4", ParseRulesError::MissingSeparator),
+            ("B2/S3", ParseRulesError::MissingPart('C')),
+            ("B2/S3/C1", ParseRulesError::InvalidStates("1".to_string())),
+            (
+                "B2/S3/C256",
+                ParseRulesError::InvalidStates("256".to_string()),
+            ),
+            (
+                "B7/S3/C3",
+                ParseRulesError::CountTooLarge { count: 7, max: 6 },
+            ),
+        ];
+
+        for (rule, error) in cases {
+            assert_eq!(rule.parse::<GenerationsRules>(), Err(error), "{rule}");
+        }
+    }
+}
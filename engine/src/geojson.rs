@@ -0,0 +1,354 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use h3o::{LatLng, VertexIndex};
+
+use crate::{game::Game, unit::CellState};
+
+// Exports the live cells of `game` as a GeoJSON FeatureCollection, one Polygon
+// per cell with its `h3` index, `age` and `marked` state. With `dissolve`, every
+// group of touching live cells becomes a single MultiPolygon instead, with the
+// number of `cells` in it, how many of them are `marked` and the `max_age`.
+//
+// Boundaries go through the same vertices the cells are drawn with. Longitudes
+// are kept continuous along a ring, so rings crossing the antimeridian have
+// some longitudes beyond ±180.
+pub fn to_geojson<S: CellState>(game: &Game<S>, dissolve: bool) -> String {
+    let features = if dissolve {
+        regions(game)
+            .iter()
+            .map(|cells| region_feature(game, cells))
+            .collect::<Vec<_>>()
+    } else {
+        (0..game.len())
+            .filter(|&slot| game.present.0[slot].is_alive())
+            .map(|slot| cell_feature(game, slot))
+            .collect()
+    };
+
+    format!(
+        r#"{{"type":"FeatureCollection","features":[{}]}}"#,
+        features.join(",")
+    )
+}
+
+fn cell_feature<S: CellState>(game: &Game<S>, slot: usize) -> String {
+    let index = game.indecies[slot];
+    let ring = index.vertexes().collect::<Vec<_>>();
+
+    format!(
+        r#"{{"type":"Feature","properties":{{"h3":"{index}","age":{},"marked":{}}},"geometry":{{"type":"Polygon","coordinates":[{}]}}}}"#,
+        game.age(slot).unwrap_or(0),
+        game.present.0[slot].marked,
+        format_ring(&unwrap_ring(&ring)),
+    )
+}
+
+fn region_feature<S: CellState>(game: &Game<S>, cells: &[usize]) -> String {
+    let polygons = dissolve(game, cells)
+        .iter()
+        .map(|polygon| {
+            let rings = polygon
+                .iter()
+                .map(|ring| format_ring(ring))
+                .collect::<Vec<_>>();
+            format!("[{}]", rings.join(","))
+        })
+        .collect::<Vec<_>>();
+    let marked = cells
+        .iter()
+        .filter(|&&slot| game.present.0[slot].marked)
+        .count();
+    let max_age = cells
+        .iter()
+        .filter_map(|&slot| game.age(slot))
+        .max()
+        .unwrap_or(0);
+
+    format!(
+        r#"{{"type":"Feature","properties":{{"cells":{},"marked":{marked},"max_age":{max_age}}},"geometry":{{"type":"MultiPolygon","coordinates":[{}]}}}}"#,
+        cells.len(),
+        polygons.join(","),
+    )
+}
+
+// Groups of live slots that are connected through neighbours.
+fn regions<S: CellState>(game: &Game<S>) -> Vec<Vec<usize>> {
+    let alive = |slot: usize| game.present.0[slot].is_alive();
+    let mut seen = vec![false; game.len()];
+    let mut regions = Vec::new();
+
+    for start in 0..game.len() {
+        if seen[start] || !alive(start) {
+            continue;
+        }
+
+        let mut region = Vec::new();
+        let mut queue = VecDeque::from([start]);
+        seen[start] = true;
+
+        while let Some(slot) = queue.pop_front() {
+            region.push(slot);
+            game.adjacency
+                .neighbours(slot)
+                .iter()
+                .for_each(|&neighbour| {
+                    let neighbour = neighbour as usize;
+                    if !seen[neighbour] && alive(neighbour) {
+                        seen[neighbour] = true;
+                        queue.push_back(neighbour);
+                    }
+                });
+        }
+
+        regions.push(region);
+    }
+
+    regions
+}
+
+// Polygons, each an exterior ring followed by its holes, covering `cells`.
+//
+// Neighbouring cells share their vertices, so an edge that two cells of the
+// region share shows up once in each direction and is dropped; the edges left
+// over form the boundary. A vertex is shared by at most three cells, so every
+// boundary vertex has a single boundary edge leaving it.
+fn dissolve<S: CellState>(game: &Game<S>, cells: &[usize]) -> Vec<Vec<Vec<(f64, f64)>>> {
+    let mut edges = HashSet::<(VertexIndex, VertexIndex)>::new();
+
+    cells.iter().for_each(|&slot| {
+        let ring = game.indecies[slot].vertexes().collect::<Vec<_>>();
+        (0..ring.len())
+            .map(|i| (ring[i], ring[(i + 1) % ring.len()]))
+            .for_each(|(from, to)| {
+                if !edges.remove(&(to, from)) {
+                    edges.insert((from, to));
+                }
+            });
+    });
+
+    let mut next = edges
+        .into_iter()
+        .collect::<HashMap<VertexIndex, VertexIndex>>();
+    let mut rings = Vec::new();
+
+    while let Some(&start) = next.keys().next() {
+        let mut ring = vec![start];
+        let mut vertex = next.remove(&start).expect("vertex with an edge");
+        while vertex != start {
+            ring.push(vertex);
+            vertex = next
+                .remove(&vertex)
+                .expect("boundary edges form closed rings");
+        }
+        rings.push(unwrap_ring(&ring));
+    }
+
+    // Exterior rings keep the winding of the cells, holes run the other way.
+    let cell_ring = game.indecies[cells[0]].vertexes().collect::<Vec<_>>();
+    let winding = signed_area(&unwrap_ring(&cell_ring)).signum();
+    let (exteriors, holes): (Vec<_>, Vec<_>) = rings
+        .into_iter()
+        .partition(|ring| signed_area(ring).signum() == winding);
+
+    let mut polygons = exteriors
+        .into_iter()
+        .map(|ring| vec![ring])
+        .collect::<Vec<_>>();
+    match polygons.first_mut() {
+        Some(polygon) => polygon.extend(holes),
+        None => polygons.extend(holes.into_iter().map(|ring| vec![ring])),
+    }
+
+    polygons
+}
+
+// Vertices as `(lng, lat)` in degrees, every longitude within 180° of the one
+// before it. A ring around a pole ends up a full turn east or west of where it
+// started, so it is closed along the nearer pole instead of back across the map.
+fn unwrap_ring(ring: &[VertexIndex]) -> Vec<(f64, f64)> {
+    let mut previous = None::<f64>;
+
+    let mut positions = ring
+        .iter()
+        .map(|&vertex| {
+            let ltln = LatLng::from(vertex);
+            let lng = match previous {
+                Some(previous) => next_to(previous, ltln.lng()),
+                None => ltln.lng(),
+            };
+            previous = Some(lng);
+            (lng, ltln.lat())
+        })
+        .collect::<Vec<_>>();
+
+    if let (Some(&(first, lat)), Some(&(last, _))) = (positions.first(), positions.last()) {
+        let end = next_to(last, first);
+        if (end - first).abs() > 180.0 {
+            let mean_lat = positions.iter().map(|&(_, lat)| lat).sum::<f64>();
+            let pole = 90f64.copysign(mean_lat);
+            positions.extend([(end, lat), (end, pole), (first, pole)]);
+        }
+    }
+
+    positions
+}
+
+// `lng` turned by whole turns to within 180° of `previous`.
+fn next_to(previous: f64, lng: f64) -> f64 {
+    previous + (lng - previous + 180.0).rem_euclid(360.0) - 180.0
+}
+
+// Twice the area the ring encloses in longitude and latitude, positive when counter-clockwise.
+fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    (0..ring.len())
+        .map(|i| (ring[i], ring[(i + 1) % ring.len()]))
+        .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+        .sum()
+}
+
+// `[[lng, lat], …]`, closed by repeating the first position.
+fn format_ring(ring: &[(f64, f64)]) -> String {
+    let positions = ring
+        .iter()
+        .chain(ring.first())
+        .map(|(lng, lat)| format!("[{lng:.6},{lat:.6}]"))
+        .collect::<Vec<_>>();
+
+    format!("[{}]", positions.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell_at(lat: f64, lng: f64) -> h3o::CellIndex {
+        LatLng::new(lat, lng).unwrap().to_cell(h3o::Resolution::Two)
+    }
+
+    fn game_with(cells: &[h3o::CellIndex]) -> Game {
+        let mut game = Game::with_seed(&h3o::Resolution::Two, 1);
+        cells
+            .iter()
+            .for_each(|cell| game.get_mut_unit(cell).unwrap().add_life());
+        game
+    }
+
+    fn slots(game: &Game, cells: &[h3o::CellIndex]) -> Vec<usize> {
+        cells
+            .iter()
+            .map(|&cell| game.slot_of(cell).unwrap())
+            .collect()
+    }
+
+    // Every `[lng, lat]` position in `json`, in order.
+    fn positions(json: &str) -> Vec<(f64, f64)> {
+        json.split('[')
+            .filter_map(|part| part.split_once(']'))
+            .filter_map(|(pair, _)| pair.split_once(','))
+            .filter_map(|(lng, lat)| Some((lng.parse().ok()?, lat.parse().ok()?)))
+            .collect()
+    }
+
+    #[test]
+    fn exports_a_cell_as_a_closed_ring() {
+        let game = game_with(&[cell_at(45.0, 10.0)]);
+        let json = to_geojson(&game, false);
+        let positions = positions(&json);
+
+        assert_eq!(json.matches(r#""type":"Polygon""#).count(), 1);
+        assert_eq!(positions.len(), 7);
+        assert_eq!(positions.first(), positions.last());
+    }
+
+    #[test]
+    fn dissolves_neighbours_into_one_ring() {
+        let cell = cell_at(45.0, 10.0);
+        let neighbour = cell.grid_disk::<Vec<_>>(1)[1];
+        let game = game_with(&[cell, neighbour]);
+
+        let polygons = dissolve(&game, &slots(&game, &[cell, neighbour]));
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].len(), 1);
+        assert_eq!(polygons[0][0].len(), 10);
+
+        let json = to_geojson(&game, true);
+        assert_eq!(json.matches(r#""type":"MultiPolygon""#).count(), 1);
+        assert_eq!(positions(&json).len(), 11);
+    }
+
+    #[test]
+    fn keeps_a_dead_cell_inside_as_a_hole() {
+        let cell = cell_at(45.0, 10.0);
+        let ring = cell
+            .grid_disk::<Vec<_>>(1)
+            .into_iter()
+            .filter(|&neighbour| neighbour != cell)
+            .collect::<Vec<_>>();
+        let game = game_with(&ring);
+
+        let polygons = dissolve(&game, &slots(&game, &ring));
+        assert_eq!(polygons.len(), 1);
+
+        let [exterior, hole] = &polygons[0][..] else {
+            panic!("expected an exterior ring and one hole");
+        };
+        assert_eq!(hole.len(), 6);
+        assert_eq!(exterior.len(), 18);
+        assert_eq!(signed_area(exterior).signum(), -signed_area(hole).signum());
+    }
+
+    #[test]
+    fn closes_rings_around_a_pole_along_the_pole() {
+        let winding = signed_area(&unwrap_ring(
+            &cell_at(45.0, 10.0).vertexes().collect::<Vec<_>>(),
+        ))
+        .signum();
+
+        for pole in [90.0, -90.0] {
+            let cell = cell_at(pole, 0.0);
+            let ring = unwrap_ring(&cell.vertexes().collect::<Vec<_>>());
+
+            assert!(ring.iter().any(|&(_, lat)| lat == pole));
+            assert_eq!(signed_area(&ring).signum(), winding, "{cell}");
+
+            // With its neighbours, the region around the pole has no hole either.
+            let disk = cell.grid_disk::<Vec<_>>(1);
+            let game = game_with(&disk);
+            let polygons = dissolve(&game, &slots(&game, &disk));
+            assert_eq!(polygons.len(), 1);
+            assert_eq!(polygons[0].len(), 1);
+            assert!(polygons[0][0].iter().any(|&(_, lat)| lat == pole));
+
+            // Without the cell itself, it is left as a hole around the pole.
+            let ring = disk
+                .iter()
+                .copied()
+                .filter(|&c| c != cell)
+                .collect::<Vec<_>>();
+            let game = game_with(&ring);
+            let polygons = dissolve(&game, &slots(&game, &ring));
+            let [exterior, hole] = &polygons[0][..] else {
+                panic!("expected an exterior ring and one hole around {cell}");
+            };
+            assert_eq!(signed_area(exterior).signum(), winding);
+            assert_eq!(signed_area(hole).signum(), -winding);
+        }
+    }
+
+    #[test]
+    fn exports_age_and_marks() {
+        let (old, young) = (cell_at(45.0, 10.0), cell_at(-30.0, 100.0));
+        let mut game = game_with(&[old]);
+        game.set_generation(3);
+        game.get_mut_unit(&young).unwrap().add_life();
+        game.mark_unit(old);
+
+        let json = to_geojson(&game, false);
+        assert!(json.contains(&format!(r#""h3":"{old}","age":3,"marked":true"#)));
+        assert!(json.contains(&format!(r#""h3":"{young}","age":0,"marked":false"#)));
+
+        let json = to_geojson(&game, true);
+        assert!(json.contains(r#""cells":1,"marked":1,"max_age":3"#));
+        assert!(json.contains(r#""cells":1,"marked":0,"max_age":0"#));
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+
+use crate::{game::Field, unit::UnitData};
+
+// The last `capacity` ticks of a game and the edits made between them, newest
+// last. Every entry keeps only the units it changed, as they were before it
+// together with the generation they were born in. Ticks also keep the random
+// generator as it was before them, so that stepping back and forth again
+// replays the same run.
+pub struct History<S = bool> {
+    entries: VecDeque<Entry<S>>,
+    capacity: usize,
+}
+
+pub struct Entry<S = bool> {
+    pub changed: Vec<(u32, UnitData<S>)>,
+    // Parallel to `changed`.
+    pub born: Vec<u64>,
+    // `None` for an edit, which does not move the generation.
+    pub rng: Option<StdRng>,
+}
+
+impl<S> Entry<S> {
+    pub fn is_tick(&self) -> bool {
+        self.rng.is_some()
+    }
+}
+
+impl<S: PartialEq + Copy> History<S> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Drops the oldest entries that no longer fit.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.make_room(0);
+    }
+
+    // The number of ticks, edits aside.
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|e| e.is_tick()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // Records the tick that turns `present` into `future`, with `born` and `rng` as they were before it.
+    pub fn record(&mut self, present: &Field<S>, future: &Field<S>, born: &[u64], rng: StdRng) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let changed = present
+            .0
+            .iter()
+            .zip(future.0.iter())
+            .enumerate()
+            .filter(|(_, (present, future))| present != future)
+            .map(|(slot, (present, _))| (slot as u32, *present))
+            .collect::<Vec<_>>();
+        let born = changed
+            .iter()
+            .map(|&(slot, _)| born[slot as usize])
+            .collect();
+
+        self.push(changed, born, Some(rng));
+    }
+
+    // Records an edit that turned the units `before` into `after`, both listing
+    // the same slots, with `born` of those slots as it was before it.
+    pub fn record_edit(
+        &mut self,
+        before: &[(u32, UnitData<S>)],
+        after: &[(u32, UnitData<S>)],
+        born: &[u64],
+    ) {
+        let (changed, born): (Vec<_>, Vec<_>) = before
+            .iter()
+            .zip(after.iter())
+            .zip(born.iter())
+            .filter(|((before, after), _)| before != after)
+            .map(|((&before, _), &born)| (before, born))
+            .unzip();
+
+        if self.capacity == 0 || changed.is_empty() {
+            return;
+        }
+        self.push(changed, born, None);
+    }
+
+    fn push(&mut self, changed: Vec<(u32, UnitData<S>)>, born: Vec<u64>, rng: Option<StdRng>) {
+        self.make_room(rng.is_some() as usize);
+        self.entries.push_back(Entry { changed, born, rng });
+    }
+
+    // Drops the oldest entries until `room` more ticks fit, only ticks count
+    // against `capacity`. Edits left in front of the oldest tick could never be
+    // stepped back over, so they go too.
+    fn make_room(&mut self, room: usize) {
+        let mut ticks = self.len();
+        while ticks + room > self.capacity || self.entries.front().is_some_and(|e| !e.is_tick()) {
+            let Some(entry) = self.entries.pop_front() else {
+                break;
+            };
+            ticks -= entry.is_tick() as usize;
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Entry<S>> {
+        self.entries.pop_back()
+    }
+}
@@ -0,0 +1,183 @@
+use std::{fmt, str::FromStr};
+
+use geo_types::{Geometry, MultiPolygon, Polygon};
+use h3o::geom::{PolyfillConfig, ToCells};
+use rand::Rng;
+
+use crate::{edit::Edit, game::Game, unit::CellState};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ImportError {
+    InvalidGeoJson(String),
+    InvalidWkt(String),
+    // The input parsed but holds no polygon to fill.
+    NoPolygon,
+    InvalidPolygon(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidGeoJson(err) => write!(f, "invalid GeoJSON: {err}"),
+            Self::InvalidWkt(err) => write!(f, "invalid WKT: {err}"),
+            Self::NoPolygon => write!(f, "no polygon to fill"),
+            Self::InvalidPolygon(err) => write!(f, "invalid polygon: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+// Reads the polygons of a GeoJSON document, or of a WKT geometry when the text
+// does not start with `{`. Coordinates are longitude and latitude in degrees;
+// every other kind of geometry is left out.
+pub fn parse_polygons(text: &str) -> Result<MultiPolygon<f64>, ImportError> {
+    let geometries = if text.trim_start().starts_with('{') {
+        parse_geojson(text)?
+    } else {
+        let wkt = wkt::Wkt::<f64>::from_str(text.trim())
+            .map_err(|err| ImportError::InvalidWkt(err.to_string()))?;
+        vec![Geometry::try_from(wkt).map_err(|err| ImportError::InvalidWkt(err.to_string()))?]
+    };
+
+    let mut polygons = Vec::new();
+    geometries
+        .into_iter()
+        .for_each(|geometry| collect_polygons(geometry, &mut polygons));
+
+    match polygons.is_empty() {
+        true => Err(ImportError::NoPolygon),
+        false => Ok(MultiPolygon(polygons)),
+    }
+}
+
+// Every cell of `resolution` whose centre lies inside one of `polygons`.
+pub fn cells_inside(
+    polygons: &MultiPolygon<f64>,
+    resolution: h3o::Resolution,
+) -> Result<Vec<h3o::CellIndex>, ImportError> {
+    let mut cells = Vec::new();
+
+    for polygon in polygons {
+        let polygon = h3o::geom::Polygon::from_degrees(polygon.clone())
+            .map_err(|err| ImportError::InvalidPolygon(err.to_string()))?;
+        cells.extend(polygon.to_cells(PolyfillConfig::new(resolution)));
+    }
+
+    cells.sort_unstable();
+    cells.dedup();
+    Ok(cells)
+}
+
+// Brings to life a `density` share of the cells of `game` inside `polygons`, so
+// that a `density` of 1 brings every cell inside to life, as one `Edit` that
+// can be undone. Returns how many cells were brought to life.
+pub fn seed_inside<S: CellState>(
+    game: &mut Game<S>,
+    polygons: &MultiPolygon<f64>,
+    density: f64,
+) -> Result<usize, ImportError> {
+    let cells = cells_inside(polygons, game.resolution)?;
+    let rng = game.rng_mut();
+    let cells = cells
+        .into_iter()
+        .filter(|_| rng.gen_bool(density.clamp(0.0, 1.0)))
+        .collect::<Vec<_>>();
+
+    let len = cells.len();
+    game.edit(Edit::Stamp(cells));
+    Ok(len)
+}
+
+fn parse_geojson(text: &str) -> Result<Vec<Geometry<f64>>, ImportError> {
+    let invalid = |err: geojson::Error| ImportError::InvalidGeoJson(err.to_string());
+
+    let geometries = match text.parse::<geojson::GeoJson>().map_err(invalid)? {
+        geojson::GeoJson::Geometry(geometry) => vec![geometry],
+        geojson::GeoJson::Feature(feature) => feature.geometry.into_iter().collect(),
+        geojson::GeoJson::FeatureCollection(collection) => collection
+            .features
+            .into_iter()
+            .filter_map(|feature| feature.geometry)
+            .collect(),
+    };
+
+    geometries
+        .into_iter()
+        .map(|geometry| Geometry::try_from(geometry).map_err(invalid))
+        .collect()
+}
+
+fn collect_polygons(geometry: Geometry<f64>, polygons: &mut Vec<Polygon<f64>>) {
+    match geometry {
+        Geometry::Polygon(polygon) => polygons.push(polygon),
+        Geometry::MultiPolygon(multi) => polygons.extend(multi),
+        Geometry::Rect(rect) => polygons.push(rect.to_polygon()),
+        Geometry::Triangle(triangle) => polygons.push(triangle.to_polygon()),
+        Geometry::GeometryCollection(collection) => collection
+            .into_iter()
+            .for_each(|geometry| collect_polygons(geometry, polygons)),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WKT: &str = "POLYGON ((10 40, 30 40, 30 55, 10 55, 10 40))";
+
+    const GEOJSON: &str = r#"{
+        "type": "Feature",
+        "properties": {"name": "box"},
+        "geometry": {
+            "type": "Polygon",
+            "coordinates": [[[10, 40], [30, 40], [30, 55], [10, 55], [10, 40]]]
+        }
+    }"#;
+
+    fn live(game: &Game) -> Vec<h3o::CellIndex> {
+        (0..game.len())
+            .filter(|&slot| game.present.0[slot].is_alive())
+            .map(|slot| game.indecies[slot])
+            .collect()
+    }
+
+    #[test]
+    fn reads_wkt_and_geojson_alike() {
+        let polygons = parse_polygons(WKT).unwrap();
+
+        assert_eq!(polygons.0.len(), 1);
+        assert_eq!(parse_polygons(GEOJSON), Ok(polygons));
+    }
+
+    #[test]
+    fn seeds_every_cell_inside_as_one_edit() {
+        let polygons = parse_polygons(GEOJSON).unwrap();
+        let mut game: Game = Game::with_seed(&h3o::Resolution::Two, 1);
+        let cells = cells_inside(&polygons, game.resolution).unwrap();
+        assert!(!cells.is_empty());
+
+        assert_eq!(seed_inside(&mut game, &polygons, 1.0), Ok(cells.len()));
+        assert_eq!(live(&game), cells);
+
+        assert!(game.undo());
+        assert!(live(&game).is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_text_and_text_without_polygons() {
+        let point = r#"{"type": "Feature", "properties": {}, "geometry": {"type": "Point", "coordinates": [10, 40]}}"#;
+
+        assert_eq!(parse_polygons("POINT (10 40)"), Err(ImportError::NoPolygon));
+        assert_eq!(parse_polygons(point), Err(ImportError::NoPolygon));
+        assert!(matches!(
+            parse_polygons("POLYGON ((10 40"),
+            Err(ImportError::InvalidWkt(_))
+        ));
+        assert!(matches!(
+            parse_polygons(r#"{"type": "Feature""#),
+            Err(ImportError::InvalidGeoJson(_))
+        ));
+    }
+}
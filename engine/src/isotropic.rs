@@ -0,0 +1,295 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    game::Unit,
+    rules::{ParseRulesError, Rule},
+};
+
+// The arrangements of live cells among six neighbours that are distinct up to
+// rotation and reflection. For two and three live neighbours, `o`rtho, `m`eta
+// and `p`ara name how far apart they are; for four they name how far apart
+// the two empty neighbours are.
+pub const CONFIGURATIONS: [(u8, Option<char>); 13] = [
+    (0, None),
+    (1, None),
+    (2, Some('o')),
+    (2, Some('m')),
+    (2, Some('p')),
+    (3, Some('o')),
+    (3, Some('m')),
+    (3, Some('p')),
+    (4, Some('o')),
+    (4, Some('m')),
+    (4, Some('p')),
+    (5, None),
+    (6, None),
+];
+
+// One neighbourhood mask per entry of `CONFIGURATIONS`, bit `k` set when the
+// k-th neighbour going around the cell is alive.
+const REPRESENTATIVES: [u8; 13] = [
+    0b000000, 0b000001, 0b000011, 0b000101, 0b001001, 0b000111, 0b001011, 0b010101, 0b111100,
+    0b111010, 0b110110, 0b011111, 0b111111,
+];
+
+// Configuration index of every six-neighbour mask.
+const CLASSES: [u8; 64] = classify_all();
+
+const fn canonical(mask: u8) -> u8 {
+    let mut best = mask;
+    let mut m = mask;
+    let mut i = 0;
+    while i < 6 {
+        m = ((m << 1) | (m >> 5)) & 0b111111;
+        let mut reflected = 0;
+        let mut bit = 0;
+        while bit < 6 {
+            reflected |= ((m >> bit) & 1) << (5 - bit);
+            bit += 1;
+        }
+        if m < best {
+            best = m;
+        }
+        if reflected < best {
+            best = reflected;
+        }
+        i += 1;
+    }
+    best
+}
+
+const fn classify_all() -> [u8; 64] {
+    let mut classes = [0; 64];
+    let mut mask = 0;
+    while mask < 64 {
+        let mut class = 0;
+        while canonical(REPRESENTATIVES[class]) != canonical(mask as u8) {
+            class += 1;
+        }
+        classes[mask] = class as u8;
+        mask += 1;
+    }
+    classes
+}
+
+// Non-totalistic hex rules in Hensel-style notation, e.g. `B2o3m/S2p34`: a digit
+// alone enables every configuration with that many live neighbours, letters
+// after it pick configurations, and a `-` before the letters excludes them.
+//
+// Pentagons have no such configurations. They fall back to counting: a count
+// is enabled on a pentagon when any configuration with that count is enabled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct IsotropicRules {
+    pub survives: [bool; 13],
+    pub emerges: [bool; 13],
+}
+
+impl IsotropicRules {
+    pub fn configuration(alive: impl Iterator<Item = bool>) -> usize {
+        let mask = alive
+            .enumerate()
+            .fold(0, |mask, (k, alive)| mask | ((alive as u8) << k));
+        CLASSES[mask as usize & 0b111111] as usize
+    }
+
+    fn table(&self, alive: bool) -> &[bool; 13] {
+        if alive {
+            &self.survives
+        } else {
+            &self.emerges
+        }
+    }
+}
+
+impl Rule for IsotropicRules {
+    fn next_state(&self, unit: &Unit) -> bool {
+        let table = self.table(unit.data.state);
+
+        if unit.is_pentagon() {
+            let n = unit.alive_neighbours() as u8;
+            return CONFIGURATIONS
+                .iter()
+                .zip(table.iter())
+                .any(|((count, _), &enabled)| *count == n && enabled);
+        }
+
+        table[Self::configuration(unit.get_neighbours().map(|n| n.state))]
+    }
+}
+
+impl Default for IsotropicRules {
+    // Same as the totalistic `3,5/2`.
+    fn default() -> Self {
+        "B2/S35".parse().expect("valid default rule")
+    }
+}
+
+impl FromStr for IsotropicRules {
+    type Err = ParseRulesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lhs, rhs) = s
+            .trim()
+            .split_once('/')
+            .ok_or(ParseRulesError::MissingSeparator)?;
+
+        let mut survives = None;
+        let mut emerges = None;
+
+        for part in [lhs.trim(), rhs.trim()] {
+            let mut chars = part.chars();
+            let (slot, name) = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => (&mut emerges, 'B'),
+                Some('S') => (&mut survives, 'S'),
+                _ => return Err(ParseRulesError::UnknownPart(part.to_string())),
+            };
+
+            if slot.is_some() {
+                return Err(ParseRulesError::DuplicatePart(name));
+            }
+            *slot = Some(parse_configurations(chars.as_str())?);
+        }
+
+        Ok(Self {
+            survives: survives.ok_or(ParseRulesError::MissingPart('S'))?,
+            emerges: emerges.ok_or(ParseRulesError::MissingPart('B'))?,
+        })
+    }
+}
+
+impl fmt::Display for IsotropicRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "B{}/S{}",
+            format_configurations(&self.emerges),
+            format_configurations(&self.survives)
+        )
+    }
+}
+
+fn parse_configurations(s: &str) -> Result<[bool; 13], ParseRulesError> {
+    let mut table = [false; 13];
+    let mut chars = s.trim().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let n = c
+            .to_digit(10)
+            .ok_or_else(|| ParseRulesError::InvalidCount(c.to_string()))?;
+        if n > 6 {
+            return Err(ParseRulesError::CountTooLarge { count: n, max: 6 });
+        }
+
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut letters = Vec::new();
+        while let Some(letter) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            letters.push(letter.to_ascii_lowercase());
+        }
+
+        for letter in &letters {
+            if !CONFIGURATIONS.contains(&(n as u8, Some(*letter))) {
+                return Err(ParseRulesError::InvalidConfiguration(format!(
+                    "{n}{letter}"
+                )));
+            }
+        }
+
+        CONFIGURATIONS
+            .iter()
+            .zip(table.iter_mut())
+            .filter(|((count, _), _)| *count == n as u8)
+            .for_each(|((_, letter), enabled)| {
+                let listed = letter.is_some_and(|l| letters.contains(&l));
+                *enabled |= letters.is_empty() || listed != negated;
+            });
+    }
+
+    Ok(table)
+}
+
+fn format_configurations(table: &[bool; 13]) -> String {
+    (0..=6u8)
+        .map(|n| {
+            let of_count = CONFIGURATIONS
+                .iter()
+                .zip(table.iter())
+                .filter(|((count, _), _)| *count == n)
+                .collect::<Vec<_>>();
+
+            if of_count.iter().all(|(_, &enabled)| enabled) {
+                n.to_string()
+            } else if of_count.iter().any(|(_, &enabled)| enabled) {
+                of_count
+                    .iter()
+                    .filter(|(_, &enabled)| enabled)
+                    .filter_map(|((_, letter), _)| *letter)
+                    .fold(n.to_string(), |s, letter| s + &letter.to_string())
+            } else {
+                String::new()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configuration_of(mask: u8) -> usize {
+        IsotropicRules::configuration((0..6).map(|k| mask & (1 << k) != 0))
+    }
+
+    #[test]
+    fn classifies_up_to_rotation_and_reflection() {
+        for (class, &representative) in REPRESENTATIVES.iter().enumerate() {
+            let mut mask = representative;
+            for _ in 0..6 {
+                mask = ((mask << 1) | (mask >> 5)) & 0b111111;
+                let reflected = (0..6).fold(0, |r, k| r | (((mask >> k) & 1) << (5 - k)));
+                assert_eq!(configuration_of(mask), class, "{mask:06b}");
+                assert_eq!(configuration_of(reflected), class, "{reflected:06b}");
+            }
+        }
+    }
+
+    #[test]
+    fn parses_letters_and_exclusions() {
+        let parse = |rule: &str| rule.parse::<IsotropicRules>().unwrap();
+
+        assert_eq!(parse("B2/S35"), IsotropicRules::default());
+        assert_eq!(parse("B2-o/S"), parse("B2mp/S"));
+        assert_eq!(parse("b2O3m/s2p34"), parse("B2o3m/S2p34"));
+    }
+
+    #[test]
+    fn formats_what_it_parses() {
+        for rule in ["B2/S35", "B2o3m/S2p34", "B2mp/S", "B0123456/S"] {
+            let rules = rule.parse::<IsotropicRules>().unwrap();
+            assert_eq!(rules.to_string(), rule);
+            assert_eq!(rules.to_string().parse(), Ok(rules));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let cases = [
+            ("B2S3", ParseRulesError::MissingSeparator),
+            ("S2/S3", ParseRulesError::DuplicatePart('S')),
+            ("B2/X3", ParseRulesError::UnknownPart("X3".to_string())),
+            ("B2/S-", ParseRulesError::InvalidCount("-".to_string())),
+            ("B7/S3", ParseRulesError::CountTooLarge { count: 7, max: 6 }),
+            (
+                "B1o/S3",
+                ParseRulesError::InvalidConfiguration("1o".to_string()),
+            ),
+            (
+                "B2x/S3",
+                ParseRulesError::InvalidConfiguration("2x".to_string()),
+            ),
+        ];
+
+        for (rule, error) in cases {
+            assert_eq!(rule.parse::<IsotropicRules>(), Err(error), "{rule}");
+        }
+    }
+}
@@ -0,0 +1,234 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{
+    adjacency::Adjacency,
+    game::{Field, Unit},
+    rules::{count_mask, Rule, SimpleRules},
+    unit::{CellState, UnitData},
+};
+
+// A backend that computes the whole `future` generation from `present`.
+pub trait Kernel<S: CellState, R: ?Sized> {
+    fn tick(
+        &mut self,
+        indecies: &[h3o::CellIndex],
+        adjacency: &Adjacency,
+        present: &Field<S>,
+        future: &mut Field<S>,
+        rules: &R,
+        seed: u64,
+    );
+}
+
+// Evaluates every unit through `Unit::transform`.
+pub struct CellKernel;
+
+impl<S: CellState, R: Rule<S> + ?Sized> Kernel<S, R> for CellKernel {
+    fn tick(
+        &mut self,
+        indecies: &[h3o::CellIndex],
+        adjacency: &Adjacency,
+        present: &Field<S>,
+        future: &mut Field<S>,
+        rules: &R,
+        seed: u64,
+    ) {
+        let step = |(slot, future): (usize, &mut UnitData<S>)| {
+            *future = Unit::new(present, adjacency, indecies, slot)
+                .with_seed(seed)
+                .transform(rules);
+        };
+
+        // Every slot only reads `present` and writes its own `future` unit, so
+        // splitting the slots across threads gives the same result as the serial loop.
+        #[cfg(feature = "parallel")]
+        future.0.par_iter_mut().enumerate().for_each(step);
+        #[cfg(not(feature = "parallel"))]
+        future.0.iter_mut().enumerate().for_each(step);
+    }
+}
+
+const MAX_NEIGHBOURS: usize = 6;
+
+// Keeps only whether a unit is alive, one bit per slot, and counts neighbours
+// 64 slots at a time with bit-sliced adders. Can be stepped on its own for
+// many generations before the result is stored back into a `Field`.
+//
+// Gathering the neighbour bits is most of the work. On one core at resolution 5
+// a step takes about 21 ms, and a full tick of a `Game`, which keeps the bits
+// between ticks but still stores them into the field, about 35 ms against
+// 60 ms through `CellKernel`.
+pub struct BitKernel {
+    len: usize,
+    // The neighbours of `slot`, side by side so that a slot reads them from one
+    // cache line. Pentagons point their missing sixth neighbour at slot `len`,
+    // whose bit is always clear.
+    neighbours: Vec<[u32; MAX_NEIGHBOURS]>,
+    // Evaluated one by one after each step, see `SimpleRules::pentagons`.
+    pentagons: Vec<u32>,
+    alive: Vec<u64>,
+    next: Vec<u64>,
+}
+
+impl BitKernel {
+    pub fn new(adjacency: &Adjacency) -> Self {
+        let len = adjacency.len();
+        let neighbours = (0..len)
+            .map(|slot| {
+                let mut row = [len as u32; MAX_NEIGHBOURS];
+                row.iter_mut()
+                    .zip(adjacency.neighbours(slot))
+                    .for_each(|(entry, &neighbour)| *entry = neighbour);
+                row
+            })
+            .collect();
+
+        let pentagons = (0..len)
+            .filter(|&slot| adjacency.neighbours(slot).len() < MAX_NEIGHBOURS)
+            .map(|slot| slot as u32)
+            .collect();
+
+        // One spare bit for the always-dead slot `len`.
+        let words = len / 64 + 1;
+
+        Self {
+            len,
+            neighbours,
+            pentagons,
+            alive: vec![0; words],
+            next: vec![0; words],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_alive(&self, slot: usize) -> bool {
+        slot < self.len && self.alive[slot / 64] & (1 << (slot % 64)) != 0
+    }
+
+    pub fn population(&self) -> usize {
+        self.alive.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn load<S: CellState>(&mut self, field: &Field<S>) {
+        self.alive.iter_mut().for_each(|w| *w = 0);
+        field
+            .0
+            .iter()
+            .enumerate()
+            .filter(|(_, unit)| unit.is_alive())
+            .for_each(|(slot, _)| self.alive[slot / 64] |= 1 << (slot % 64));
+    }
+
+    // Writes the current bits into `future`, carrying over everything else from `present`.
+    pub fn store<S: CellState>(&self, present: &Field<S>, future: &mut Field<S>) {
+        future
+            .0
+            .iter_mut()
+            .zip(present.0.iter())
+            .enumerate()
+            .for_each(|(slot, (future, present))| {
+                *future = present.with_set_life(self.is_alive(slot));
+            });
+    }
+
+    pub fn step(&mut self, rules: &SimpleRules) {
+        let survives = count_mask(&rules.survives);
+        let emerges = count_mask(&rules.emerges);
+        let (len, neighbours, alive) = (self.len, &self.neighbours, &self.alive);
+
+        let step = |(word, next): (usize, &mut u64)| {
+            let mut planes = [0u64; MAX_NEIGHBOURS];
+            let rows = &neighbours[(word * 64).min(len)..((word + 1) * 64).min(len)];
+            rows.iter().enumerate().for_each(|(bit, row)| {
+                planes.iter_mut().zip(row).for_each(|(plane, &neighbour)| {
+                    let neighbour = neighbour as usize;
+                    *plane |= ((alive[neighbour / 64] >> (neighbour % 64)) & 1) << bit;
+                });
+            });
+
+            let counts = count_bits(&planes);
+            let survivors = select_counts(&counts, survives);
+            let newborns = select_counts(&counts, emerges);
+            let current = alive[word];
+
+            *next = (current & survivors) | (!current & newborns);
+        };
+
+        #[cfg(feature = "parallel")]
+        self.next.par_iter_mut().enumerate().for_each(step);
+        #[cfg(not(feature = "parallel"))]
+        self.next.iter_mut().enumerate().for_each(step);
+
+        for &slot in &self.pentagons {
+            let slot = slot as usize;
+            let n = self.neighbours[slot]
+                .iter()
+                .filter(|&&neighbour| self.is_alive(neighbour as usize))
+                .count();
+            let is_alive = rules
+                .apply_pentagon(n, self.is_alive(slot))
+                .unwrap_or(false);
+
+            self.next[slot / 64] &= !(1 << (slot % 64));
+            self.next[slot / 64] |= (is_alive as u64) << (slot % 64);
+        }
+
+        // Keep the padding bits, including the always-dead slot, clear.
+        if let Some(last) = self.next.last_mut() {
+            *last &= (1 << (len % 64)) - 1;
+        }
+
+        std::mem::swap(&mut self.alive, &mut self.next);
+    }
+}
+
+// Only exact for binary states, see `CellState::BINARY`.
+impl<S: CellState> Kernel<S, SimpleRules> for BitKernel {
+    fn tick(
+        &mut self,
+        _indecies: &[h3o::CellIndex],
+        _adjacency: &Adjacency,
+        present: &Field<S>,
+        future: &mut Field<S>,
+        rules: &SimpleRules,
+        _seed: u64,
+    ) {
+        self.load(present);
+        self.step(rules);
+        self.store(present, future);
+    }
+}
+
+// Adds six one-bit planes into a three-bit count per lane.
+fn count_bits(planes: &[u64; MAX_NEIGHBOURS]) -> [u64; 3] {
+    let full_add = |a: u64, b: u64, c: u64| (a ^ b ^ c, (a & b) | (c & (a ^ b)));
+
+    let (s1, c1) = full_add(planes[0], planes[1], planes[2]);
+    let (s2, c2) = full_add(planes[3], planes[4], planes[5]);
+
+    let bit0 = s1 ^ s2;
+    let carry = s1 & s2;
+    let (bit1, bit2) = full_add(c1, c2, carry);
+
+    [bit0, bit1, bit2]
+}
+
+// Lanes whose count is one of the counts set in `mask`.
+fn select_counts(counts: &[u64; 3], mask: u8) -> u64 {
+    (0..=MAX_NEIGHBOURS)
+        .filter(|n| mask & (1 << n) != 0)
+        .fold(0, |selected, n| {
+            selected
+                | counts.iter().enumerate().fold(!0, |lanes, (bit, &count)| {
+                    lanes & if n & (1 << bit) != 0 { count } else { !count }
+                })
+        })
+}
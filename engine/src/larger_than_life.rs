@@ -0,0 +1,211 @@
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+use crate::{
+    game::Unit,
+    rules::{ParseRulesError, Rule},
+};
+
+// Larger than Life on the hex grid, in Golly's notation with a hexagonal
+// neighbourhood: `R2,C0,M0,S4..8,B5..6,NH`. `R` is the radius of the
+// neighbourhood, `M1` counts the cell itself, `S` and `B` are the inclusive
+// ranges of live counts for survival and birth. Only two states (`C0` or `C2`)
+// are supported.
+//
+// Counts are absolute, so cells whose neighbourhood reaches over a pentagon,
+// see `Unit::neighbourhood_size`, need fewer free cells to reach the same count.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LargerThanLifeRules {
+    pub radius: u32,
+    pub middle: bool,
+    pub survives: RangeInclusive<u32>,
+    pub emerges: RangeInclusive<u32>,
+}
+
+impl LargerThanLifeRules {
+    // Cells in a full hexagonal neighbourhood, the centre not included.
+    pub fn neighbourhood_size(&self) -> u32 {
+        3 * self.radius * (self.radius + 1)
+    }
+}
+
+impl Rule for LargerThanLifeRules {
+    fn next_state(&self, unit: &Unit) -> bool {
+        let alive = unit.data.state;
+        let n = unit.alive_neighbours() as u32 + (self.middle && alive) as u32;
+
+        if alive {
+            self.survives.contains(&n)
+        } else {
+            self.emerges.contains(&n)
+        }
+    }
+
+    fn radius(&self) -> u32 {
+        self.radius
+    }
+}
+
+impl FromStr for LargerThanLifeRules {
+    type Err = ParseRulesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut radius = None;
+        let mut middle = false;
+        let mut survives = None;
+        let mut emerges = None;
+
+        for part in s.trim().split(',').map(str::trim) {
+            let (key, value) = part.split_at(part.chars().next().map_or(0, char::len_utf8));
+
+            match key.to_ascii_uppercase().as_str() {
+                "R" => {
+                    radius = Some(
+                        value
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|&r| (1..=10).contains(&r))
+                            .ok_or_else(|| ParseRulesError::InvalidRadius(value.to_string()))?,
+                    )
+                }
+                "C" => match value {
+                    "0" | "2" => (),
+                    _ => return Err(ParseRulesError::InvalidStates(value.to_string())),
+                },
+                "M" => {
+                    middle = match value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(ParseRulesError::InvalidCount(part.to_string())),
+                    }
+                }
+                "S" => survives = Some(parse_range(value)?),
+                "B" => emerges = Some(parse_range(value)?),
+                "N" if value.eq_ignore_ascii_case("H") => (),
+                _ => return Err(ParseRulesError::UnknownPart(part.to_string())),
+            }
+        }
+
+        let rules = Self {
+            radius: radius.ok_or(ParseRulesError::MissingPart('R'))?,
+            middle,
+            survives: survives.ok_or(ParseRulesError::MissingPart('S'))?,
+            emerges: emerges.ok_or(ParseRulesError::MissingPart('B'))?,
+        };
+
+        let max = rules.neighbourhood_size() + rules.middle as u32;
+        let largest = *rules.survives.end().max(rules.emerges.end());
+        if largest > max {
+            return Err(ParseRulesError::CountTooLarge {
+                count: largest,
+                max,
+            });
+        }
+
+        Ok(rules)
+    }
+}
+
+impl fmt::Display for LargerThanLifeRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R{},C0,M{},S{}..{},B{}..{},NH",
+            self.radius,
+            self.middle as u8,
+            self.survives.start(),
+            self.survives.end(),
+            self.emerges.start(),
+            self.emerges.end()
+        )
+    }
+}
+
+// `4..8`, or `4` for a single count.
+fn parse_range(s: &str) -> Result<RangeInclusive<u32>, ParseRulesError> {
+    let count = |c: &str| {
+        c.trim()
+            .parse::<u32>()
+            .map_err(|_| ParseRulesError::InvalidCount(c.to_string()))
+    };
+
+    match s.split_once("..") {
+        Some((from, to)) => Ok(count(from)?..=count(to)?),
+        None => count(s).map(|n| n..=n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_golly_notation() {
+        let rules = "r2, c2, m1, s4..8, b5, nh"
+            .parse::<LargerThanLifeRules>()
+            .unwrap();
+
+        assert_eq!(
+            rules,
+            LargerThanLifeRules {
+                radius: 2,
+                middle: true,
+                survives: 4..=8,
+                emerges: 5..=5,
+            }
+        );
+    }
+
+    #[test]
+    fn formats_what_it_parses() {
+        for rule in ["R2,C0,M0,S4..8,B5..6,NH", "R10,C0,M1,S0..331,B1..1,NH"] {
+            let rules = rule.parse::<LargerThanLifeRules>().unwrap();
+            assert_eq!(rules.to_string(), rule);
+            assert_eq!(rules.to_string().parse(), Ok(rules));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let cases = [
+            ("C0,S4..8,B5..6", ParseRulesError::MissingPart('R')),
+            ("R2,B5..6", ParseRulesError::MissingPart('S')),
+            ("R2,S4..8", ParseRulesError::MissingPart('B')),
+            (
+                "R0,S4..8,B5..6",
+                ParseRulesError::InvalidRadius("0".to_string()),
+            ),
+            (
+                "R11,S4..8,B5..6",
+                ParseRulesError::InvalidRadius("11".to_string()),
+            ),
+            (
+                "R2,C3,S4..8,B5..6",
+                ParseRulesError::InvalidStates("3".to_string()),
+            ),
+            (
+                "R2,M2,S4..8,B5..6",
+                ParseRulesError::InvalidCount("M2".to_string()),
+            ),
+            (
+                "R2,S4..x,B5..6",
+                ParseRulesError::InvalidCount("x".to_string()),
+            ),
+            (
+                "R2,S4..8,B5..6,NM",
+                ParseRulesError::UnknownPart("NM".to_string()),
+            ),
+            (
+                "R2,S4..8,B5..19",
+                ParseRulesError::CountTooLarge { count: 19, max: 18 },
+            ),
+            (
+                "R2,M1,S4..20,B5",
+                ParseRulesError::CountTooLarge { count: 20, max: 19 },
+            ),
+        ];
+
+        for (rule, error) in cases {
+            assert_eq!(rule.parse::<LargerThanLifeRules>(), Err(error), "{rule}");
+        }
+    }
+}
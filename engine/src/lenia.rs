@@ -0,0 +1,91 @@
+use crate::{
+    data,
+    game::Unit,
+    rules::Rule,
+    unit::{lerp_colors, CellState},
+};
+
+// A continuous state, from empty at `0.0` to fully alive at `1.0`.
+impl CellState for f32 {
+    fn alive() -> Self {
+        1.0
+    }
+
+    fn is_alive(&self) -> bool {
+        *self > 0.0
+    }
+
+    fn color(&self, is_penta: bool) -> [f32; 4] {
+        lerp_colors(
+            &false.color(is_penta),
+            &data::UNIT_COLOR,
+            self.clamp(0.0, 1.0),
+        )
+    }
+}
+
+// Lenia on the hex grid: every cell averages its neighbours within `radius`
+// rings, weighted by a smooth bump peaking on the middle ring, and moves by
+// `dt` towards alive or empty depending on how close that average is to `mu`.
+//
+// The average is taken over the cells that exist, so neighbourhoods reaching
+// over a pentagon are not starved.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LeniaRules {
+    radius: u32,
+    pub mu: f32,
+    pub sigma: f32,
+    pub dt: f32,
+    // Weight of every neighbour on ring `d`, at `shell[d - 1]`.
+    shell: Vec<f32>,
+}
+
+impl LeniaRules {
+    pub fn new(radius: u32, mu: f32, sigma: f32, dt: f32) -> Self {
+        let radius = radius.max(1);
+        let shell = (1..=radius)
+            .map(|d| {
+                let r = (d as f32 - 0.5) / radius as f32;
+                (4.0 - 1.0 / (r * (1.0 - r))).exp()
+            })
+            .collect();
+
+        Self {
+            radius,
+            mu,
+            sigma,
+            dt,
+            shell,
+        }
+    }
+
+    // Maps an average in `[0, 1]` to a change in `[-1, 1]`.
+    pub fn growth(&self, u: f32) -> f32 {
+        let x = (u - self.mu) / self.sigma;
+        2.0 * (-x * x / 2.0).exp() - 1.0
+    }
+}
+
+impl Rule<f32> for LeniaRules {
+    fn next_state(&self, unit: &Unit<f32>) -> f32 {
+        let (sum, total) =
+            unit.get_neighbours_with_distance()
+                .fold((0.0, 0.0), |(sum, total), (neighbour, d)| {
+                    let weight = self.shell[d as usize - 1];
+                    (sum + weight * neighbour.state, total + weight)
+                });
+        let u = if total > 0.0 { sum / total } else { 0.0 };
+
+        (unit.data.state + self.dt * self.growth(u)).clamp(0.0, 1.0)
+    }
+
+    fn radius(&self) -> u32 {
+        self.radius
+    }
+}
+
+impl Default for LeniaRules {
+    fn default() -> Self {
+        Self::new(4, 0.15, 0.017, 0.1)
+    }
+}
@@ -1,5 +1,21 @@
+pub mod active;
+pub mod adjacency;
 pub mod data;
+pub mod edit;
 pub mod game;
+pub mod generations;
+pub mod geojson;
+pub mod history;
+#[cfg(feature = "import")]
+pub mod import;
+pub mod isotropic;
+pub mod kernel;
+pub mod larger_than_life;
+pub mod lenia;
 pub mod pattern;
+pub mod pattern_file;
 pub mod unit;
 pub mod rules;
+pub mod save;
+pub mod species;
+pub mod stochastic;
@@ -1,307 +1,413 @@
-use std::{collections::HashMap, vec};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
 use h3o::CellIndex;
 
 pub trait Pattern {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex>;
+    // Best effort: the cells of the pattern turned to `orientation` around
+    // `center` that can be placed, and the offsets of the ones that can not.
+    fn place(&self, center: &h3o::CellIndex, orientation: Orientation) -> Placement;
 
     fn size(&self) -> usize;
-}
-
-struct SingleCell;
 
-impl Pattern for SingleCell {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex> {
-        vec![*center]
+    fn as_cells(&self, center: &h3o::CellIndex) -> Result<Vec<h3o::CellIndex>, PlacementError> {
+        self.as_oriented_cells(center, Orientation::default())
     }
 
-    fn size(&self) -> usize {
-        1
+    // All the cells of the pattern, or an error when any of them can not be placed.
+    fn as_oriented_cells(
+        &self,
+        center: &h3o::CellIndex,
+        orientation: Orientation,
+    ) -> Result<Vec<h3o::CellIndex>, PlacementError> {
+        self.place(center, orientation).complete(*center)
     }
-
-    
 }
 
-struct Star;
-
-impl Pattern for Star {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex> {
-        center
-            .grid_disk::<Vec<_>>(1)
-            .into_iter()
-            .filter(|idx| idx != center)
-            .collect()
-    }
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Placement {
+    pub cells: Vec<h3o::CellIndex>,
+    // Oriented IJ offsets from the centre of the cells that were left out, either
+    // because they can not be reached or because they land on a cell placed
+    // for an earlier offset.
+    pub skipped: Vec<(i32, i32)>,
+}
 
-    fn size(&self) -> usize {
-        7
+impl Placement {
+    pub fn complete(self, center: h3o::CellIndex) -> Result<Vec<h3o::CellIndex>, PlacementError> {
+        match self.skipped.is_empty() {
+            true => Ok(self.cells),
+            false => Err(PlacementError::Unreachable {
+                center,
+                skipped: self.skipped,
+            }),
+        }
     }
-
-    
-
-    
 }
 
-struct SmallPulsar {
-    pattern: [CellIndex; 3],
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PlacementError {
+    // Local IJ coordinates do not reach across pentagon distortion or between
+    // far-apart base cells, or fold several offsets onto one cell near pentagons.
+    Unreachable {
+        center: h3o::CellIndex,
+        skipped: Vec<(i32, i32)>,
+    },
 }
 
-impl SmallPulsar {
-    fn new() -> Self {
-        Self {
-            pattern: [
-                h3o::CellIndex::try_from(0x8201a7fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x8201affffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x820117fffffffff).expect("Invalid literal for cell"),
-            ],
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreachable { center, skipped } => write!(
+                f,
+                "{} cell(s) can not be placed around {center}, across a pentagon or too far away",
+                skipped.len()
+            ),
         }
     }
 }
 
-impl Pattern for SmallPulsar {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex> {
-        transpose_pattern(self.pattern[0], *center, &self.pattern)
-    }
+impl std::error::Error for PlacementError {}
 
-    fn size(&self) -> usize {
-        3
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CaptureError {
+    NoCells,
+    // Cells the anchor has no local IJ coordinates for.
+    Unreachable(Vec<h3o::CellIndex>),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCells => write!(f, "there are no live marked cells to capture"),
+            Self::Unreachable(cells) => write!(
+                f,
+                "{} cell(s) are too far apart or across a pentagon to capture",
+                cells.len()
+            ),
+        }
     }
-    
 }
 
-struct MediumWiggler {
-    pattern: [CellIndex; 4],
+impl std::error::Error for CaptureError {}
+
+// One of the twelve ways to lay a pattern on the hex grid: mirrored across the
+// I = J diagonal when `mirrored` is set, then turned `rotation` times by 60 degrees.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Orientation {
+    pub rotation: u8,
+    pub mirrored: bool,
 }
 
-impl MediumWiggler {
-    fn new() -> Self {
+impl Orientation {
+    pub fn rotated(self) -> Self {
         Self {
-            pattern: [
-                h3o::CellIndex::try_from(0x827c67fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x827c77fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x827c57fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x827c5ffffffffff).expect("Invalid literal for cell"),
-            ],
+            rotation: (self.rotation + 1) % 6,
+            ..self
         }
     }
-}
 
-impl Pattern for MediumWiggler {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex> {
-        transpose_pattern(self.pattern[0], *center, &self.pattern)
+    pub fn rotated_back(self) -> Self {
+        Self {
+            rotation: (self.rotation + 5) % 6,
+            ..self
+        }
     }
 
-    fn size(&self) -> usize {
-        4
+    pub fn mirrored(self) -> Self {
+        Self {
+            mirrored: !self.mirrored,
+            ..self
+        }
     }
 
-    
-}
-
-struct SmallFlicker {
-    pattern: [CellIndex; 2],
+    // The neighbours of (0, 0) going around it are (1, 0), (1, 1), (0, 1), (-1, 0),
+    // (-1, -1) and (0, -1), so a turn by one of them is `(i, j) -> (i - j, i)`.
+    pub fn apply(self, (i, j): (i32, i32)) -> (i32, i32) {
+        let mirrored = if self.mirrored { (j, i) } else { (i, j) };
+        (0..self.rotation % 6).fold(mirrored, |(i, j), _| (i - j, i))
+    }
 }
 
-impl SmallFlicker {
-    fn new() -> Self {
-        Self {
-            pattern: [
-                h3o::CellIndex::try_from(0x8208a7fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x820837fffffffff).expect("Invalid literal for cell"),
-            ],
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}°", self.rotation as u32 % 6 * 60)?;
+        if self.mirrored {
+            write!(f, ", mirrored")?;
         }
+        Ok(())
     }
 }
 
-impl Pattern for SmallFlicker {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex> {
-        transpose_pattern(self.pattern[0], *center, &self.pattern)
+struct SingleCell;
+
+impl Pattern for SingleCell {
+    fn place(&self, center: &h3o::CellIndex, _orientation: Orientation) -> Placement {
+        Placement {
+            cells: vec![*center],
+            skipped: vec![],
+        }
     }
 
     fn size(&self) -> usize {
-        2
+        1
     }
-
-    
 }
 
-struct RotatingTrio {
-    pattern: [CellIndex; 4],
-}
+struct Star;
 
-impl RotatingTrio {
-    fn new() -> Self {
-        Self {
-            //821317fffffffff
-            pattern: [
-                h3o::CellIndex::try_from(0x821317fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x8213a7fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82131ffffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x821337fffffffff).expect("Invalid literal for cell"),
-            ],
+impl Pattern for Star {
+    fn place(&self, center: &h3o::CellIndex, _orientation: Orientation) -> Placement {
+        Placement {
+            cells: center
+                .grid_disk::<Vec<_>>(1)
+                .into_iter()
+                .filter(|idx| idx != center)
+                .collect(),
+            skipped: vec![],
         }
     }
-}
-
-impl Pattern for RotatingTrio {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex> {
-        transpose_pattern(self.pattern[0], *center, &self.pattern[1..])
-    }
 
     fn size(&self) -> usize {
-        3
+        7
     }
-
-
-    
 }
 
-struct Blob {
-    pattern: [CellIndex; 7],
+// A pattern as offsets in the local IJ coordinates of the cell it is placed on.
+// Neighbouring cells are one step apart in IJ at every resolution, so the same
+// offsets give the same shape on any grid, except near pentagons, where some
+// offsets can not be reached from the centre.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct IjPattern {
+    offsets: Vec<(i32, i32)>,
 }
 
-impl Blob {
-    fn new() -> Self {
+impl IjPattern {
+    pub fn new(offsets: impl Into<Vec<(i32, i32)>>) -> Self {
         Self {
-            //821317fffffffff
-            pattern: [
-                h3o::CellIndex::try_from(0x82c067fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82c147fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82c14ffffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82ad6ffffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82c077fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82c047fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82d137fffffffff).expect("Invalid literal for cell"),
-            ],
+            offsets: offsets.into(),
         }
     }
-}
 
-impl Pattern for Blob {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex> {
-        transpose_pattern(self.pattern[0], *center, &self.pattern[1..])
+    pub fn offsets(&self) -> &[(i32, i32)] {
+        &self.offsets
     }
 
-    fn size(&self) -> usize {
-        6
+    // The pattern `cells` make, anchored at the cell with the smallest total grid
+    // distance to the others, so that it is placed around the cursor.
+    pub fn capture(cells: &[h3o::CellIndex]) -> Result<Self, CaptureError> {
+        let anchor = cells
+            .iter()
+            .min_by_key(|a| {
+                cells
+                    .iter()
+                    .map(|b| a.grid_distance(*b).unwrap_or(i32::MAX) as i64)
+                    .sum::<i64>()
+            })
+            .ok_or(CaptureError::NoCells)?;
+
+        let ij = |cell: &CellIndex| cell.to_local_ij(*anchor).map(|ij| (ij.i(), ij.j()));
+        let (i0, j0) = ij(anchor).map_err(|_| CaptureError::Unreachable(vec![*anchor]))?;
+
+        let (offsets, unreachable): (Vec<_>, Vec<_>) = cells
+            .iter()
+            .map(|cell| (cell, ij(cell)))
+            .partition(|(_, ij)| ij.is_ok());
+
+        match unreachable.is_empty() {
+            true => Ok(Self::new(
+                offsets
+                    .into_iter()
+                    .filter_map(|(_, ij)| ij.ok())
+                    .map(|(i, j)| (i - i0, j - j0))
+                    .collect::<Vec<_>>(),
+            )),
+            false => Err(CaptureError::Unreachable(
+                unreachable.into_iter().map(|(&cell, _)| cell).collect(),
+            )),
+        }
     }
 
-    
-}
-
-struct LittleBlob {
-    pattern: [CellIndex; 5],
-}
-
-impl LittleBlob {
-    fn new() -> Self {
+    pub fn oriented(&self, orientation: Orientation) -> Self {
         Self {
-            //821317fffffffff
-            pattern: [
-                h3o::CellIndex::try_from(0x82a627fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82a62ffffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82a777fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82a71ffffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82a637fffffffff).expect("Invalid literal for cell"),
-            ],
+            offsets: self.offsets.iter().map(|&o| orientation.apply(o)).collect(),
         }
     }
 }
 
-impl Pattern for LittleBlob {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex> {
-        transpose_pattern(self.pattern[0], *center, &self.pattern[1..])
+impl Pattern for IjPattern {
+    fn place(&self, center: &h3o::CellIndex, orientation: Orientation) -> Placement {
+        let origin = center.to_local_ij(*center).ok().map(|ij| (ij.i(), ij.j()));
+        let mut placement = Placement::default();
+        let mut placed = HashSet::new();
+
+        for (i, j) in self.offsets.iter().map(|&offset| orientation.apply(offset)) {
+            let cell = origin.and_then(|(i0, j0)| {
+                CellIndex::try_from(h3o::LocalIJ::new_unchecked(*center, i0 + i, j0 + j)).ok()
+            });
+
+            // Near pentagons two offsets can come out as the same cell.
+            match cell.filter(|&cell| placed.insert(cell)) {
+                Some(cell) => placement.cells.push(cell),
+                None => placement.skipped.push((i, j)),
+            }
+        }
+
+        placement
     }
 
     fn size(&self) -> usize {
-        4
+        self.offsets.len()
     }
-
-    
 }
 
-struct Glider {
-    pattern: [CellIndex; 11],
-}
+const SMALL_PULSAR: [(i32, i32); 3] = [(0, 0), (-1, -1), (0, -1)];
 
-impl Glider {
-    fn new() -> Self {
-        Self {
-            //821317fffffffff
-            pattern: [
-                h3o::CellIndex::try_from(0x82130ffffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x821327fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x8202dffffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x821347fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x82122ffffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x821237fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x821317fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x8212affffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x8212e7fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x821267fffffffff).expect("Invalid literal for cell"),
-                h3o::CellIndex::try_from(0x821387fffffffff).expect("Invalid literal for cell"),
-            ],
-        }
-    }
-}
+const MEDIUM_WIGGLER: [(i32, i32); 4] = [(0, 0), (0, 1), (-1, 1), (-2, 0)];
 
-impl Pattern for Glider {
-    fn as_cells(&self, center: &h3o::CellIndex) -> Vec<h3o::CellIndex> {
-        transpose_pattern(self.pattern[0], *center, &self.pattern[1..])
-    }
+const SMALL_FLICKER: [(i32, i32); 2] = [(0, 0), (1, -1)];
 
-    fn size(&self) -> usize {
-        10
-    }
+const ROTATING_TRIO: [(i32, i32); 3] = [(0, 1), (-1, -1), (1, 0)];
 
-    
-}
+const BLOB: [(i32, i32); 6] = [(2, 1), (1, 0), (0, -1), (0, 1), (-1, 0), (-2, -2)];
 
-fn transpose_pattern(src: CellIndex, dst: CellIndex, pattern: &[CellIndex]) -> Vec<CellIndex> {
-    // Compute translation offset.
-    let src_coord = src.to_local_ij(src).expect("src coord");
-    let dst_coord = dst.to_local_ij(dst).expect("dst coord");
-    let i_offset = dst_coord.i() - src_coord.i();
-    let j_offset = dst_coord.j() - src_coord.j();
-
-    // Transpose the pattern from src to dst.
-    pattern
-        .iter()
-        .copied()
-        .map(|cell| {
-            // Compute the local IJ coordinate wrt original center cell.
-            let src_ij = cell.to_local_ij(src).expect("local IJ");
-            // Apply translation and re-anchor at destination center cell.
-            let dst_ij =
-                h3o::LocalIJ::new_unchecked(dst, src_ij.i() + i_offset, src_ij.j() + j_offset);
-            // Convert back to cell index.
-            CellIndex::try_from(dst_ij).expect("dst cell")
-        })
-        .collect::<Vec<_>>()
-}
+const LITTLE_BLOB: [(i32, i32); 4] = [(-1, -1), (1, -1), (1, 1), (0, 1)];
+
+const GLIDER: [(i32, i32); 10] = [
+    (2, 1),
+    (1, -1),
+    (-1, -2),
+    (-2, -1),
+    (-1, 1),
+    (1, 2),
+    (-3, 1),
+    (-4, -1),
+    (-3, -3),
+    (0, 3),
+];
 
 pub fn create_pattern_map() -> Vec<(&'static str, Box<dyn Pattern>)> {
     let mut pattern_map: HashMap<&'static str, Box<dyn Pattern>> = HashMap::new();
 
     pattern_map.insert("Single cell", Box::new(SingleCell));
     pattern_map.insert("Star", Box::new(Star));
-    pattern_map.insert("Small pulsar", Box::new(SmallPulsar::new()));
-    pattern_map.insert("Small flicker", Box::new(SmallFlicker::new()));
-    pattern_map.insert("Rotating trio", Box::new(RotatingTrio::new()));
-    pattern_map.insert("Medium wiggler", Box::new(MediumWiggler::new()));
-    pattern_map.insert("Blob", Box::new(Blob::new()));
-    pattern_map.insert("Little blob", Box::new(LittleBlob::new()));
-    pattern_map.insert("Glider", Box::new(Glider::new()));
+    pattern_map.insert("Small pulsar", Box::new(IjPattern::new(SMALL_PULSAR)));
+    pattern_map.insert("Small flicker", Box::new(IjPattern::new(SMALL_FLICKER)));
+    pattern_map.insert("Rotating trio", Box::new(IjPattern::new(ROTATING_TRIO)));
+    pattern_map.insert("Medium wiggler", Box::new(IjPattern::new(MEDIUM_WIGGLER)));
+    pattern_map.insert("Blob", Box::new(IjPattern::new(BLOB)));
+    pattern_map.insert("Little blob", Box::new(IjPattern::new(LITTLE_BLOB)));
+    pattern_map.insert("Glider", Box::new(IjPattern::new(GLIDER)));
 
     let mut patterns: Vec<_> = pattern_map.into_iter().collect();
 
     patterns.sort_by(|(k1, p1), (k2, p2)| {
-        if p1.size() != p2.size(){
+        if p1.size() != p2.size() {
             p1.size().cmp(&p2.size())
-        }else{
+        } else {
             k1.cmp(k2)
         }
     });
 
     patterns
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RING: [(i32, i32); 6] = [(1, 0), (1, 1), (0, 1), (-1, 0), (-1, -1), (0, -1)];
+
+    #[test]
+    fn six_turns_are_no_turn() {
+        for start in [Orientation::default(), Orientation::default().mirrored()] {
+            let turned = (0..6).fold(start, |orientation, _| orientation.rotated());
+            assert_eq!(turned, start);
+            assert_eq!(start.rotated().rotated_back(), start);
+
+            let turn = Orientation::default().rotated();
+            for &offset in disk().offsets() {
+                let laid = start.apply(offset);
+                assert_eq!((0..6).fold(laid, |o, _| turn.apply(o)), laid);
+            }
+        }
+    }
+
+    #[test]
+    fn a_turn_moves_every_neighbour_to_the_next() {
+        let turn = Orientation::default().rotated();
+        for k in 0..RING.len() {
+            assert_eq!(turn.apply(RING[k]), RING[(k + 1) % RING.len()]);
+        }
+    }
+
+    #[test]
+    fn mirroring_twice_is_no_mirroring() {
+        let twice = Orientation::default().mirrored().mirrored();
+        assert_eq!(twice, Orientation::default());
+
+        let mirror = Orientation::default().mirrored();
+        for offset in disk().offsets() {
+            assert_eq!(mirror.apply(mirror.apply(*offset)), *offset);
+            assert_eq!(twice.apply(*offset), *offset);
+        }
+        assert!(RING
+            .iter()
+            .all(|offset| RING.contains(&mirror.apply(*offset))));
+    }
+
+    // Every offset within three rings of the centre.
+    fn disk() -> IjPattern {
+        IjPattern::new(
+            (-3..=3)
+                .flat_map(|i| (-3..=3).map(move |j| (i, j)))
+                .filter(|&(i, j): &(i32, i32)| (i - j).abs() <= 3)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn places_every_offset_once_away_from_pentagons() {
+        let center = CellIndex::base_cells()
+            .find(|cell| !cell.is_pentagon())
+            .and_then(|cell| cell.center_child(h3o::Resolution::Four))
+            .unwrap();
+        let mut cells = disk().as_cells(&center).unwrap();
+        let mut expected = center.grid_disk::<Vec<_>>(3);
+
+        expected.sort_unstable();
+        cells.sort_unstable();
+        assert_eq!(cells, expected);
+    }
+
+    #[test]
+    fn skips_offsets_that_fold_onto_one_cell_near_pentagons() {
+        let pattern = disk();
+        let mut skipped = 0;
+
+        for pentagon in CellIndex::base_cells().filter(|cell| cell.is_pentagon()) {
+            let pentagon = pentagon.center_child(h3o::Resolution::Two).unwrap();
+            for center in pentagon.grid_disk::<Vec<_>>(2) {
+                let placement = pattern.place(&center, Orientation::default());
+                let unique = placement.cells.iter().collect::<HashSet<_>>();
+
+                assert_eq!(unique.len(), placement.cells.len(), "{center}");
+                assert_eq!(
+                    placement.cells.len() + placement.skipped.len(),
+                    pattern.size()
+                );
+                skipped += placement.skipped.len();
+
+                if !placement.skipped.is_empty() {
+                    assert!(matches!(
+                        placement.complete(center),
+                        Err(PlacementError::Unreachable { .. })
+                    ));
+                }
+            }
+        }
+        assert!(skipped > 0);
+    }
+}
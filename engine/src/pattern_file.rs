@@ -0,0 +1,287 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use crate::pattern::{IjPattern, Orientation, Pattern, Placement};
+
+// A plain-text pattern, in the spirit of `.cells` files:
+//
+//     !Name: Small pulsar
+//     !Author: Someone
+//     !Rule: B2/S35
+//     !Origin: 1 1
+//     ! Any other line starting with `!` is a comment.
+//     OO
+//     .O
+//
+// Every other line is a row of the picture, `O` for a live cell and `.` for a
+// dead one. The column is the I and the row the J axial (local IJ) offset, so
+// the neighbours of a cell are the cells left, right, above and below it plus
+// the ones up-left and down-right. `Origin` is the column and row of the cell
+// placed under the cursor, the top-left one when it is missing. Spaces are
+// ignored, so rows can be indented to show the skew of the grid.
+//
+// The rule is kept as written, it may belong to any of the rule families.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PatternFile {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub rule: Option<String>,
+    pub comments: Vec<String>,
+    pub pattern: IjPattern,
+}
+
+pub const EXTENSION: &str = "hexcells";
+
+const ALIVE: char = 'O';
+const DEAD: char = '.';
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PatternFileError {
+    Io(String),
+    DuplicateHeader(String),
+    InvalidOrigin(String),
+    InvalidCell { line: usize, cell: char },
+    NoCells,
+}
+
+impl fmt::Display for PatternFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "can not access the pattern file: {err}"),
+            Self::DuplicateHeader(key) => write!(f, "'{key}' is given twice"),
+            Self::InvalidOrigin(origin) => {
+                write!(f, "'{origin}' is not a column and a row")
+            }
+            Self::InvalidCell { line, cell } => {
+                write!(f, "line {line}: '{cell}' is neither '{ALIVE}' nor '{DEAD}'")
+            }
+            Self::NoCells => write!(f, "the pattern has no live cells"),
+        }
+    }
+}
+
+impl std::error::Error for PatternFileError {}
+
+impl PatternFile {
+    pub fn new(pattern: IjPattern) -> Self {
+        Self {
+            pattern,
+            ..Default::default()
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PatternFileError> {
+        std::fs::read_to_string(path)
+            .map_err(|err| PatternFileError::Io(err.to_string()))?
+            .parse()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PatternFileError> {
+        std::fs::write(path, self.to_string()).map_err(|err| PatternFileError::Io(err.to_string()))
+    }
+}
+
+impl Pattern for PatternFile {
+    fn place(&self, center: &h3o::CellIndex, orientation: Orientation) -> Placement {
+        self.pattern.place(center, orientation)
+    }
+
+    fn size(&self) -> usize {
+        self.pattern.size()
+    }
+}
+
+impl FromStr for PatternFile {
+    type Err = PatternFileError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut file = Self::default();
+        let mut origin = None;
+        let mut cells = Vec::new();
+        let mut row = 0;
+
+        for (n, line) in s.lines().enumerate() {
+            let line = line.trim_end();
+
+            if let Some(text) = line.strip_prefix('!') {
+                let header = text.split_once(':').and_then(|(key, value)| {
+                    let slot = match key.to_ascii_lowercase().as_str() {
+                        "name" => &mut file.name,
+                        "author" => &mut file.author,
+                        "rule" => &mut file.rule,
+                        "origin" => &mut origin,
+                        _ => return None,
+                    };
+                    Some((key, slot, value.trim()))
+                });
+
+                match header {
+                    Some((key, slot, _)) if slot.is_some() => {
+                        return Err(PatternFileError::DuplicateHeader(key.to_string()))
+                    }
+                    Some((_, slot, value)) => *slot = Some(value.to_string()),
+                    None => file
+                        .comments
+                        .push(text.strip_prefix(' ').unwrap_or(text).to_string()),
+                }
+                continue;
+            }
+
+            for (column, cell) in line.chars().filter(|c| *c != ' ').enumerate() {
+                match cell {
+                    ALIVE => cells.push((column as i32, row)),
+                    DEAD => (),
+                    cell => return Err(PatternFileError::InvalidCell { line: n + 1, cell }),
+                }
+            }
+            row += 1;
+        }
+
+        if cells.is_empty() {
+            return Err(PatternFileError::NoCells);
+        }
+
+        let (i, j) = match origin {
+            Some(origin) => parse_origin(&origin)?,
+            None => (0, 0),
+        };
+
+        file.pattern = IjPattern::new(
+            cells
+                .into_iter()
+                .map(|(column, row)| (column - i, row - j))
+                .collect::<Vec<_>>(),
+        );
+
+        Ok(file)
+    }
+}
+
+fn parse_origin(origin: &str) -> Result<(i32, i32), PatternFileError> {
+    let mut parts = origin.split_whitespace().map(|part| part.parse::<i32>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(column)), Some(Ok(row)), None) => Ok((column, row)),
+        _ => Err(PatternFileError::InvalidOrigin(origin.to_string())),
+    }
+}
+
+impl fmt::Display for PatternFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers = [
+            ("Name", &self.name),
+            ("Author", &self.author),
+            ("Rule", &self.rule),
+        ];
+        for (key, value) in headers {
+            if let Some(value) = value {
+                writeln!(f, "!{key}: {value}")?;
+            }
+        }
+
+        let offsets = self.pattern.offsets();
+        let min_i = offsets.iter().map(|&(i, _)| i).min().unwrap_or(0);
+        let min_j = offsets.iter().map(|&(_, j)| j).min().unwrap_or(0);
+        let max_j = offsets.iter().map(|&(_, j)| j).max().unwrap_or(0);
+
+        writeln!(f, "!Origin: {} {}", -min_i, -min_j)?;
+
+        for comment in &self.comments {
+            writeln!(f, "! {comment}")?;
+        }
+
+        for j in min_j..=max_j {
+            let mut columns: Vec<_> = offsets
+                .iter()
+                .filter(|&&(_, row)| row == j)
+                .map(|&(i, _)| (i - min_i) as usize)
+                .collect();
+            columns.sort_unstable();
+
+            let mut line = String::new();
+            for column in columns {
+                while line.len() < column {
+                    line.push(DEAD);
+                }
+                if line.len() == column {
+                    line.push(ALIVE);
+                }
+            }
+
+            // Keep empty rows, otherwise the rows below would move up.
+            if line.is_empty() {
+                line.push(DEAD);
+            }
+            writeln!(f, "{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PULSAR: &str = "\
+!Name: Small pulsar
+!Author: Someone
+!Rule: B2/S35
+! Just a comment.
+!Origin: 1 1
+ OO
+ .O
+";
+
+    #[test]
+    fn parses_headers_and_cells() {
+        let file = PULSAR.parse::<PatternFile>().unwrap();
+
+        assert_eq!(file.name.as_deref(), Some("Small pulsar"));
+        assert_eq!(file.author.as_deref(), Some("Someone"));
+        assert_eq!(file.rule.as_deref(), Some("B2/S35"));
+        assert_eq!(file.comments, ["Just a comment."]);
+
+        let mut offsets = file.pattern.offsets().to_vec();
+        offsets.sort_unstable();
+        assert_eq!(offsets, [(-1, -1), (0, -1), (0, 0)]);
+    }
+
+    #[test]
+    fn writes_what_it_parses() {
+        let file = PULSAR.parse::<PatternFile>().unwrap();
+        assert_eq!(file.to_string().parse(), Ok(file));
+
+        let gap = "!Origin: 0 0\nO\n.\n.O\n".parse::<PatternFile>().unwrap();
+        assert_eq!(gap.to_string(), "!Origin: 0 0\nO\n.\n.O\n");
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let cases = [
+            (
+                "!Name: a\n!name: b\nO",
+                PatternFileError::DuplicateHeader("name".to_string()),
+            ),
+            (
+                "!Origin: 1\nO",
+                PatternFileError::InvalidOrigin("1".to_string()),
+            ),
+            (
+                "!Origin: 1 x\nO",
+                PatternFileError::InvalidOrigin("1 x".to_string()),
+            ),
+            (
+                "O.\n.*",
+                PatternFileError::InvalidCell { line: 2, cell: '*' },
+            ),
+            ("!Name: Nothing\n..\n", PatternFileError::NoCells),
+        ];
+
+        for (text, error) in cases {
+            assert_eq!(text.parse::<PatternFile>(), Err(error), "{text}");
+        }
+        assert!(matches!(
+            PatternFile::load("does/not/exist.hexcells"),
+            Err(PatternFileError::Io(_))
+        ));
+    }
+}
@@ -1,6 +1,48 @@
+use std::{fmt, str::FromStr};
+
+use crate::{game::Unit, unit::CellState};
+
+// Computes the next state of a unit from the unit and its neighbourhood.
+pub trait Rule<S: CellState = bool>: Sync {
+    fn next_state(&self, unit: &Unit<S>) -> S;
+
+    // How many rings of cells around a unit `Unit::get_neighbours` goes through.
+    fn radius(&self) -> u32 {
+        1
+    }
+
+    // Whether the next state only depends on the neighbourhood. Rules that draw
+    // from `Unit::rng` return `false`, which makes every tick a full one.
+    fn is_deterministic(&self) -> bool {
+        true
+    }
+
+    // Rules that are plain two-state `SimpleRules` can run on the bit-packed kernel.
+    fn as_simple(&self) -> Option<&SimpleRules> {
+        None
+    }
+}
+
+// How the 12 pentagons, which only have 5 neighbours, are treated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PentagonPolicy {
+    // Look the count up in the same tables as every other cell.
+    #[default]
+    SameTable,
+    // Look the count up in tables of their own.
+    Separate {
+        survives: [bool; 7],
+        emerges: [bool; 7],
+    },
+    // Scale the count from 0..=5 to 0..=6, rounding to the nearest, before the lookup.
+    Rescale,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct SimpleRules {
     pub survives: [bool; 7],
     pub emerges: [bool; 7],
+    pub pentagons: PentagonPolicy,
 }
 
 impl SimpleRules {
@@ -12,6 +54,46 @@ impl SimpleRules {
         }
         .copied()
     }
+
+    pub fn apply_pentagon(&self, n: usize, alive: bool) -> Option<bool> {
+        match &self.pentagons {
+            PentagonPolicy::SameTable => self.apply(n, alive),
+            PentagonPolicy::Separate { survives, emerges } => if alive {
+                survives.get(n)
+            } else {
+                emerges.get(n)
+            }
+            .copied(),
+            PentagonPolicy::Rescale => self.apply((n * 6 + 2) / 5, alive),
+        }
+    }
+}
+
+// Bit `n` is set when the table allows `n` live neighbours, e.g. for the bit
+// kernel and saved worlds.
+pub(crate) fn count_mask(table: &[bool; 7]) -> u8 {
+    table
+        .iter()
+        .enumerate()
+        .filter(|(_, &v)| v)
+        .fold(0, |mask, (n, _)| mask | (1 << n))
+}
+
+impl Rule for SimpleRules {
+    fn next_state(&self, unit: &Unit) -> bool {
+        let n = unit.alive_neighbours();
+
+        if unit.is_pentagon() {
+            self.apply_pentagon(n, unit.data.state)
+        } else {
+            self.apply(n, unit.data.state)
+        }
+        .unwrap_or(false)
+    }
+
+    fn as_simple(&self) -> Option<&SimpleRules> {
+        Some(self)
+    }
 }
 
 impl Default for SimpleRules {
@@ -25,6 +107,217 @@ impl Default for SimpleRules {
 
         emerges[2] = true;
 
-        Self { survives, emerges }
+        Self {
+            survives,
+            emerges,
+            pentagons: PentagonPolicy::default(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseRulesError {
+    MissingSeparator,
+    MissingPart(char),
+    DuplicatePart(char),
+    UnknownPart(String),
+    InvalidCount(String),
+    CountTooLarge { count: u32, max: u32 },
+    InvalidStates(String),
+    InvalidConfiguration(String),
+    InvalidRadius(String),
+}
+
+impl fmt::Display for ParseRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeparator => write!(f, "expected '/' between the two halves of the rule"),
+            Self::MissingPart(part) => write!(f, "missing '{part}' part of the rule"),
+            Self::DuplicatePart(part) => write!(f, "'{part}' part is given twice"),
+            Self::UnknownPart(part) => write!(f, "'{part}' is not a part of the rule"),
+            Self::InvalidCount(count) => write!(f, "'{count}' is not a neighbour count"),
+            Self::CountTooLarge { count, max } => {
+                write!(
+                    f,
+                    "neighbour count {count} is above {max}, the size of the neighbourhood"
+                )
+            }
+            Self::InvalidConfiguration(configuration) => {
+                write!(
+                    f,
+                    "'{configuration}' is not a hex neighbourhood configuration"
+                )
+            }
+            Self::InvalidRadius(radius) => {
+                write!(
+                    f,
+                    "'{radius}' is not a neighbourhood radius between 1 and 10"
+                )
+            }
+            Self::InvalidStates(states) => {
+                write!(f, "'{states}' is not a number of states between 2 and 255")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseRulesError {}
+
+// Accepts both `B2/S35` (in either order, any case) and the survive/birth form `3,5/2`.
+impl FromStr for SimpleRules {
+    type Err = ParseRulesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lhs, rhs) = s
+            .trim()
+            .split_once('/')
+            .ok_or(ParseRulesError::MissingSeparator)?;
+        let (survives, emerges) = parse_survives_emerges(lhs, rhs)?;
+
+        Ok(Self {
+            survives,
+            emerges,
+            pentagons: PentagonPolicy::default(),
+        })
+    }
+}
+
+// Parses the two halves of a rule, `B…` and `S…` in any order or `survives/emerges`.
+pub(crate) fn parse_survives_emerges(
+    lhs: &str,
+    rhs: &str,
+) -> Result<([bool; 7], [bool; 7]), ParseRulesError> {
+    let (lhs, rhs) = (lhs.trim(), rhs.trim());
+
+    let is_prefixed = |part: &str| part.starts_with(|c: char| "BbSs".contains(c));
+
+    if !is_prefixed(lhs) && !is_prefixed(rhs) {
+        return Ok((parse_listed_counts(lhs)?, parse_listed_counts(rhs)?));
+    }
+
+    let mut survives = None;
+    let mut emerges = None;
+
+    for part in [lhs, rhs] {
+        let mut chars = part.chars();
+        let (slot, name) = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('B') => (&mut emerges, 'B'),
+            Some('S') => (&mut survives, 'S'),
+            _ => return Err(ParseRulesError::UnknownPart(part.to_string())),
+        };
+
+        if slot.is_some() {
+            return Err(ParseRulesError::DuplicatePart(name));
+        }
+        *slot = Some(parse_digit_counts(chars.as_str())?);
+    }
+
+    Ok((
+        survives.ok_or(ParseRulesError::MissingPart('S'))?,
+        emerges.ok_or(ParseRulesError::MissingPart('B'))?,
+    ))
+}
+
+impl fmt::Display for SimpleRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "B{}/S{}",
+            format_counts(&self.emerges),
+            format_counts(&self.survives)
+        )
+    }
+}
+
+pub(crate) fn format_counts(table: &[bool; 7]) -> String {
+    table
+        .iter()
+        .enumerate()
+        .filter(|(_, &v)| v)
+        .map(|(n, _)| n.to_string())
+        .collect()
+}
+
+fn set_count(table: &mut [bool; 7], n: u32) -> Result<(), ParseRulesError> {
+    *table
+        .get_mut(n as usize)
+        .ok_or(ParseRulesError::CountTooLarge { count: n, max: 6 })? = true;
+    Ok(())
+}
+
+// `235`, one digit per count.
+fn parse_digit_counts(s: &str) -> Result<[bool; 7], ParseRulesError> {
+    let mut table = [false; 7];
+
+    for c in s.trim().chars() {
+        let n = c
+            .to_digit(10)
+            .ok_or_else(|| ParseRulesError::InvalidCount(c.to_string()))?;
+        set_count(&mut table, n)?;
+    }
+
+    Ok(table)
+}
+
+// `3,5`, comma separated counts, or `35` without separators.
+fn parse_listed_counts(s: &str) -> Result<[bool; 7], ParseRulesError> {
+    if !s.contains(',') {
+        return parse_digit_counts(s);
+    }
+
+    let mut table = [false; 7];
+
+    for count in s.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        let n = count
+            .parse::<u32>()
+            .map_err(|_| ParseRulesError::InvalidCount(count.to_string()))?;
+        set_count(&mut table, n)?;
+    }
+
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_notation() {
+        for rule in ["B2/S35", "s35/b2", " B2 / S35 ", "3,5/2", "35/2"] {
+            assert_eq!(
+                rule.parse::<SimpleRules>(),
+                Ok(SimpleRules::default()),
+                "{rule}"
+            );
+        }
+    }
+
+    #[test]
+    fn formats_what_it_parses() {
+        for rule in ["B2/S35", "B/S", "B0123456/S0123456", "B13/S0246"] {
+            let rules = rule.parse::<SimpleRules>().unwrap();
+            assert_eq!(rules.to_string(), rule);
+            assert_eq!(rules.to_string().parse(), Ok(rules));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let cases = [
+            ("B2S35", ParseRulesError::MissingSeparator),
+            ("B2/B3", ParseRulesError::DuplicatePart('B')),
+            ("B2/X3", ParseRulesError::UnknownPart("X3".to_string())),
+            ("B2/S3x", ParseRulesError::InvalidCount("x".to_string())),
+            ("3,x/2", ParseRulesError::InvalidCount("x".to_string())),
+            ("B7/S3", ParseRulesError::CountTooLarge { count: 7, max: 6 }),
+            (
+                "3,12/2",
+                ParseRulesError::CountTooLarge { count: 12, max: 6 },
+            ),
+        ];
+
+        for (rule, error) in cases {
+            assert_eq!(rule.parse::<SimpleRules>(), Err(error), "{rule}");
+        }
     }
 }
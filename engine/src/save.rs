@@ -0,0 +1,352 @@
+use std::fmt;
+
+use crate::{
+    game::{as_number, Game},
+    rules::{count_mask, PentagonPolicy, SimpleRules},
+};
+
+// A saved world, all integers little-endian:
+//
+//   magic       b"HEXL"
+//   version     u8, `VERSION`
+//   resolution  u8
+//   rules       u8 survives mask, u8 emerges mask, u8 pentagon policy
+//               (0 same table, 1 separate, 2 rescale), and for separate
+//               tables two more masks; bit `n` stands for `n` neighbours
+//   generation  u64
+//   seed        u64
+//   live cells  LEB128 count, then the sorted H3 indices as LEB128
+//               differences from the previous one, the first from 0
+//
+// Marks are not saved. The seed is the one the game was created or last
+// reseeded with, so random draws after loading start over from it.
+const MAGIC: &[u8; 4] = b"HEXL";
+pub const VERSION: u8 = 1;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LoadError {
+    NotAWorld,
+    UnsupportedVersion(u8),
+    Truncated,
+    // A LEB128 number runs over 64 bits.
+    NumberTooLong,
+    InvalidResolution(u8),
+    InvalidCountMask(u8),
+    InvalidPentagonPolicy(u8),
+    InvalidCell(u64),
+    // The difference to the next cell takes it past the largest 64-bit number.
+    CellOverflow { previous: u64, delta: u64 },
+    // The cell is valid but does not belong to the grid of the saved resolution.
+    CellOfOtherResolution(u64),
+    UnsortedCells(u64),
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAWorld => write!(f, "not a saved world"),
+            Self::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "saved with version {version}, only version {VERSION} can be loaded"
+                )
+            }
+            Self::Truncated => write!(f, "the saved world ends too early"),
+            Self::NumberTooLong => write!(f, "a number does not fit in 64 bits"),
+            Self::InvalidResolution(resolution) => {
+                write!(f, "{resolution} is not an H3 resolution")
+            }
+            Self::InvalidCountMask(mask) => {
+                write!(f, "{mask:#010b} has neighbour counts above 6")
+            }
+            Self::InvalidPentagonPolicy(policy) => {
+                write!(f, "{policy} is not a pentagon policy")
+            }
+            Self::InvalidCell(cell) => write!(f, "{cell:#x} is not an H3 cell"),
+            Self::CellOverflow { previous, delta } => {
+                write!(
+                    f,
+                    "cell {previous:#x} plus {delta:#x} does not fit in 64 bits"
+                )
+            }
+            Self::CellOfOtherResolution(cell) => {
+                write!(f, "cell {cell:#x} is not of the saved resolution")
+            }
+            Self::UnsortedCells(cell) => {
+                write!(f, "cell {cell:#x} is not above the cell before it")
+            }
+            Self::TrailingBytes(len) => write!(f, "{len} bytes after the saved world"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+pub fn save(game: &Game, rules: &SimpleRules) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+
+    bytes.push(VERSION);
+    bytes.push(as_number(&game.resolution) as u8);
+
+    bytes.push(count_mask(&rules.survives));
+    bytes.push(count_mask(&rules.emerges));
+    match &rules.pentagons {
+        PentagonPolicy::SameTable => bytes.push(0),
+        PentagonPolicy::Separate { survives, emerges } => {
+            bytes.extend([1, count_mask(survives), count_mask(emerges)])
+        }
+        PentagonPolicy::Rescale => bytes.push(2),
+    }
+
+    bytes.extend(game.generation().to_le_bytes());
+    bytes.extend(game.seed().to_le_bytes());
+
+    // Slots follow the order of the indices, so the live cells come out sorted.
+    let live = game
+        .indecies
+        .iter()
+        .zip(game.present.0.iter())
+        .filter(|(_, unit)| unit.is_alive())
+        .map(|(&index, _)| u64::from(index))
+        .collect::<Vec<_>>();
+
+    write_varint(&mut bytes, live.len() as u64);
+    live.iter().fold(0, |previous, &cell| {
+        write_varint(&mut bytes, cell - previous);
+        cell
+    });
+
+    bytes
+}
+
+pub fn load(bytes: &[u8]) -> Result<(Game, SimpleRules), LoadError> {
+    let mut reader = Reader(bytes);
+
+    if reader.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+        return Err(LoadError::NotAWorld);
+    }
+    match reader.byte()? {
+        VERSION => (),
+        version => return Err(LoadError::UnsupportedVersion(version)),
+    }
+
+    let resolution = reader.byte()?;
+    let resolution = h3o::Resolution::try_from(resolution)
+        .map_err(|_| LoadError::InvalidResolution(resolution))?;
+
+    let survives = reader.counts()?;
+    let emerges = reader.counts()?;
+    let pentagons = match reader.byte()? {
+        0 => PentagonPolicy::SameTable,
+        1 => PentagonPolicy::Separate {
+            survives: reader.counts()?,
+            emerges: reader.counts()?,
+        },
+        2 => PentagonPolicy::Rescale,
+        policy => return Err(LoadError::InvalidPentagonPolicy(policy)),
+    };
+    let rules = SimpleRules {
+        survives,
+        emerges,
+        pentagons,
+    };
+
+    let generation = reader.u64()?;
+    let seed = reader.u64()?;
+
+    let mut game = Game::with_seed(&resolution, seed);
+    game.set_generation(generation);
+
+    let mut cell = 0u64;
+    for i in 0..reader.varint()? {
+        let delta = reader.varint()?;
+        if i > 0 && delta == 0 {
+            return Err(LoadError::UnsortedCells(cell));
+        }
+        cell = cell.checked_add(delta).ok_or(LoadError::CellOverflow {
+            previous: cell,
+            delta,
+        })?;
+
+        let index = h3o::CellIndex::try_from(cell).map_err(|_| LoadError::InvalidCell(cell))?;
+        game.get_mut_unit(&index)
+            .ok_or(LoadError::CellOfOtherResolution(cell))?
+            .add_life();
+    }
+
+    match reader.0.len() {
+        0 => Ok((game, rules)),
+        len => Err(LoadError::TrailingBytes(len)),
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+        if self.0.len() < len {
+            return Err(LoadError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, LoadError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("eight bytes")))
+    }
+
+    fn varint(&mut self) -> Result<u64, LoadError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            // The tenth byte only has room for the top bit.
+            if shift == 63 && byte > 1 {
+                return Err(LoadError::NumberTooLong);
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(LoadError::NumberTooLong)
+    }
+
+    fn counts(&mut self) -> Result<[bool; 7], LoadError> {
+        let mask = self.byte()?;
+        if mask >> 7 != 0 {
+            return Err(LoadError::InvalidCountMask(mask));
+        }
+        Ok(std::array::from_fn(|n| mask & (1 << n) != 0))
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world() -> (Game, SimpleRules) {
+        let mut game: Game = Game::with_seed(&h3o::Resolution::One, 3).with_spawned_life();
+        game.set_generation(42);
+
+        let rules = SimpleRules {
+            pentagons: PentagonPolicy::Separate {
+                survives: [false, true, false, false, false, true, false],
+                emerges: [false, false, true, false, false, false, false],
+            },
+            ..Default::default()
+        };
+        (game, rules)
+    }
+
+    fn live(game: &Game) -> Vec<bool> {
+        game.present.0.iter().map(|unit| unit.is_alive()).collect()
+    }
+
+    // Everything before the live cells of a resolution 1 world whose rules enable no counts.
+    fn header() -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([VERSION, 1, 0, 0, 0]);
+        bytes.extend([0; 16]);
+        bytes
+    }
+
+    fn with_cells(deltas: &[u64]) -> Vec<u8> {
+        let mut bytes = header();
+        write_varint(&mut bytes, deltas.len() as u64);
+        deltas
+            .iter()
+            .for_each(|&delta| write_varint(&mut bytes, delta));
+        bytes
+    }
+
+    fn cell(resolution: h3o::Resolution) -> u64 {
+        h3o::CellIndex::base_cells()
+            .flat_map(|index| index.children(resolution))
+            .map(u64::from)
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn loads_what_it_saves() {
+        let (game, rules) = world();
+        let (loaded, loaded_rules) = load(&save(&game, &rules)).unwrap();
+
+        assert_eq!(loaded_rules, rules);
+        assert_eq!(loaded.resolution, game.resolution);
+        assert_eq!(loaded.generation(), game.generation());
+        assert_eq!(loaded.seed(), game.seed());
+        assert_eq!(live(&loaded), live(&game));
+    }
+
+    #[test]
+    fn reads_every_varint() {
+        for value in [0, 1, 0x7f, 0x80, 1 << 63, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value);
+            assert_eq!(Reader(&bytes).varint(), Ok(value));
+        }
+
+        let mut too_long = vec![0xff; 9];
+        too_long.push(0x02);
+        assert_eq!(Reader(&too_long).varint(), Err(LoadError::NumberTooLong));
+        assert_eq!(Reader(&[0x80; 11]).varint(), Err(LoadError::NumberTooLong));
+    }
+
+    #[test]
+    fn rejects_invalid_worlds() {
+        let (game, rules) = world();
+        let saved = save(&game, &rules);
+        let first = cell(h3o::Resolution::One);
+
+        let patch = |at: usize, byte: u8| {
+            let mut bytes = saved.clone();
+            bytes[at] = byte;
+            bytes
+        };
+        let mut trailing = saved.clone();
+        trailing.push(0);
+
+        let cases = [
+            (b"HEXA".to_vec(), LoadError::NotAWorld),
+            (patch(4, 2), LoadError::UnsupportedVersion(2)),
+            (saved[..saved.len() - 1].to_vec(), LoadError::Truncated),
+            (patch(5, 16), LoadError::InvalidResolution(16)),
+            (patch(6, 0x80), LoadError::InvalidCountMask(0x80)),
+            (patch(8, 3), LoadError::InvalidPentagonPolicy(3)),
+            (trailing, LoadError::TrailingBytes(1)),
+            (with_cells(&[5]), LoadError::InvalidCell(5)),
+            (
+                with_cells(&[first, u64::MAX]),
+                LoadError::CellOverflow {
+                    previous: first,
+                    delta: u64::MAX,
+                },
+            ),
+            (
+                with_cells(&[cell(h3o::Resolution::Two)]),
+                LoadError::CellOfOtherResolution(cell(h3o::Resolution::Two)),
+            ),
+            (with_cells(&[first, 0]), LoadError::UnsortedCells(first)),
+        ];
+
+        for (bytes, error) in cases {
+            assert_eq!(load(&bytes).err(), Some(error));
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::cmp::Reverse;
+
+use rand::Rng;
+
+use crate::{
+    data,
+    game::{Game, Unit},
+    rules::{Rule, SimpleRules},
+    unit::CellState,
+};
+
+pub const MAX_SPECIES: usize = data::SPECIES_COLORS.len();
+
+// State of a cell under multi-species rules: empty, or alive and of one species.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Species {
+    #[default]
+    Empty,
+    Alive(u8),
+}
+
+impl CellState for Species {
+    fn alive() -> Self {
+        Species::Alive(0)
+    }
+
+    fn is_alive(&self) -> bool {
+        *self != Species::Empty
+    }
+
+    fn color(&self, is_penta: bool) -> [f32; 4] {
+        match *self {
+            Species::Empty => false.color(is_penta),
+            Species::Alive(species) => data::SPECIES_COLORS[species as usize % MAX_SPECIES],
+        }
+    }
+}
+
+// Immigration and QuadLife on hex: whether a cell lives is decided by `rules`
+// from the number of live neighbours, whatever their species. Survivors keep
+// their species and a newborn takes the one most of its parents have. On a tie
+// it takes the species of the first of the tied parents going around the cell,
+// which favours none of the species. With three or more species, a newborn
+// whose parents are all of different species and miss exactly one takes that
+// one instead.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpeciesRules {
+    pub rules: SimpleRules,
+    pub species: u8,
+}
+
+impl SpeciesRules {
+    pub fn immigration(rules: SimpleRules) -> Self {
+        Self { rules, species: 2 }
+    }
+
+    pub fn quad_life(rules: SimpleRules) -> Self {
+        Self { rules, species: 4 }
+    }
+
+    // Fills `game` at its density, with species drawn evenly.
+    pub fn spawn(&self, game: &mut Game<Species>) {
+        let species = self.species();
+        game.spawn_life_with(|rng| Species::Alive(rng.gen_range(0..species as u8)));
+    }
+
+    fn species(&self) -> usize {
+        (self.species as usize).clamp(1, MAX_SPECIES)
+    }
+
+    // `parents[s]` counts the live neighbours of species `s`, `first[s]` is the
+    // position of the first of them around the cell.
+    fn newborn(&self, parents: &[usize], first: &[usize]) -> u8 {
+        let n = parents.iter().sum::<usize>();
+        let distinct = parents.iter().filter(|&&count| count > 0).count();
+
+        if parents.len() > 2 && distinct == n && n == parents.len() - 1 {
+            if let Some(missing) = parents.iter().position(|&count| count == 0) {
+                return missing as u8;
+            }
+        }
+
+        (0..parents.len())
+            .max_by_key(|&species| (parents[species], Reverse(first[species])))
+            .unwrap_or(0) as u8
+    }
+}
+
+impl Rule<Species> for SpeciesRules {
+    fn next_state(&self, unit: &Unit<Species>) -> Species {
+        let mut parents = [0; MAX_SPECIES];
+        let mut first = [usize::MAX; MAX_SPECIES];
+        unit.get_neighbours()
+            .enumerate()
+            .for_each(|(k, neighbour)| {
+                if let Species::Alive(species) = neighbour.state {
+                    let species = species as usize % MAX_SPECIES;
+                    parents[species] += 1;
+                    first[species] = first[species].min(k);
+                }
+            });
+
+        let n = parents.iter().sum();
+        let alive = unit.data.is_alive();
+        let lives = if unit.is_pentagon() {
+            self.rules.apply_pentagon(n, alive)
+        } else {
+            self.rules.apply(n, alive)
+        }
+        .unwrap_or(false);
+
+        match (unit.data.state, lives) {
+            (_, false) => Species::Empty,
+            (Species::Alive(species), true) => Species::Alive(species),
+            (Species::Empty, true) => {
+                let species = self.species();
+                Species::Alive(self.newborn(&parents[..species], &first[..species]))
+            }
+        }
+    }
+}
+
+impl Default for SpeciesRules {
+    fn default() -> Self {
+        Self::immigration(SimpleRules::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONE: usize = usize::MAX;
+
+    #[test]
+    fn newborn_takes_the_majority() {
+        let rules = SpeciesRules::immigration(SimpleRules::default());
+        assert_eq!(rules.newborn(&[1, 2], &[0, 1]), 1);
+        assert_eq!(rules.newborn(&[2, 1], &[3, 0]), 0);
+
+        let rules = SpeciesRules::quad_life(SimpleRules::default());
+        assert_eq!(rules.newborn(&[0, 2, 0, 1], &[NONE, 4, NONE, 0]), 1);
+    }
+
+    #[test]
+    fn newborn_breaks_ties_by_the_first_parent() {
+        let rules = SpeciesRules::immigration(SimpleRules::default());
+        assert_eq!(rules.newborn(&[1, 1], &[3, 1]), 1);
+        assert_eq!(rules.newborn(&[1, 1], &[0, 4]), 0);
+
+        let rules = SpeciesRules::quad_life(SimpleRules::default());
+        assert_eq!(rules.newborn(&[0, 1, 0, 1], &[NONE, 5, NONE, 2]), 3);
+    }
+
+    #[test]
+    fn quad_life_newborn_of_three_species_takes_the_missing_one() {
+        let rules = SpeciesRules::quad_life(SimpleRules::default());
+        assert_eq!(rules.newborn(&[1, 0, 1, 1], &[0, NONE, 2, 4]), 1);
+        assert_eq!(rules.newborn(&[1, 1, 1, 0], &[5, 3, 1, NONE]), 3);
+    }
+}
@@ -0,0 +1,122 @@
+use rand::Rng;
+
+use crate::{
+    game::Unit,
+    rules::{Rule, SimpleRules},
+};
+
+// Two-state rules where every neighbour count gives a probability of surviving
+// or being born instead of a yes or no. On top of that every empty cell comes
+// alive with probability `spontaneous_birth` and every live one dies with
+// probability `spontaneous_death`, whatever its neighbours.
+//
+// Random numbers come from `Unit::rng`, so a run is reproducible from the seed
+// of its `Game`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StochasticRules {
+    pub survives: [f64; 7],
+    pub emerges: [f64; 7],
+    pub spontaneous_birth: f64,
+    pub spontaneous_death: f64,
+}
+
+impl StochasticRules {
+    pub fn with_noise(mut self, spontaneous_birth: f64, spontaneous_death: f64) -> Self {
+        self.spontaneous_birth = spontaneous_birth;
+        self.spontaneous_death = spontaneous_death;
+        self
+    }
+}
+
+impl Rule for StochasticRules {
+    fn next_state(&self, unit: &Unit) -> bool {
+        let mut rng = unit.rng();
+        let n = unit.alive_neighbours();
+        let alive = unit.data.state;
+
+        let p = if alive { &self.survives } else { &self.emerges }
+            .get(n)
+            .copied()
+            .unwrap_or(0.0);
+        let drawn = rng.gen_bool(p.clamp(0.0, 1.0));
+
+        // The noise goes by the state before the draw, a live cell that failed
+        // to survive is not brought back by it.
+        if alive {
+            drawn && !rng.gen_bool(self.spontaneous_death.clamp(0.0, 1.0))
+        } else {
+            drawn || rng.gen_bool(self.spontaneous_birth.clamp(0.0, 1.0))
+        }
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+}
+
+// The same counts with every probability either 0 or 1 and no noise. Pentagons
+// always use the same table as every other cell.
+impl From<SimpleRules> for StochasticRules {
+    fn from(rules: SimpleRules) -> Self {
+        Self {
+            survives: rules.survives.map(|v| v as u8 as f64),
+            emerges: rules.emerges.map(|v| v as u8 as f64),
+            spontaneous_birth: 0.0,
+            spontaneous_death: 0.0,
+        }
+    }
+}
+
+impl Default for StochasticRules {
+    fn default() -> Self {
+        SimpleRules::default().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    fn rules() -> StochasticRules {
+        StochasticRules::default().with_noise(0.01, 0.02)
+    }
+
+    fn live(game: &Game) -> Vec<bool> {
+        game.present.0.iter().map(|unit| unit.is_alive()).collect()
+    }
+
+    // The live cells after each of `ticks` ticks.
+    fn run(game: &mut Game, ticks: usize) -> Vec<Vec<bool>> {
+        let rules = rules();
+        (0..ticks)
+            .map(|_| {
+                game.next_tick(&rules);
+                game.swap_buffers();
+                live(game)
+            })
+            .collect()
+    }
+
+    fn game(seed: u64) -> Game {
+        Game::with_seed(&h3o::Resolution::One, seed).with_spawned_life()
+    }
+
+    #[test]
+    fn replays_from_the_seed() {
+        let generations = run(&mut game(7), 20);
+
+        assert_eq!(run(&mut game(7), 20), generations);
+        assert_ne!(run(&mut game(8), 20), generations);
+    }
+
+    #[test]
+    fn replays_after_stepping_back() {
+        let mut game = game(7);
+        run(&mut game, 10);
+        let generations = run(&mut game, 10);
+
+        assert_eq!(game.step_back(10), 10);
+        assert_eq!(run(&mut game, 10), generations);
+    }
+}
@@ -1,43 +1,89 @@
 use super::data;
 
-#[derive(Clone, Copy)]
-pub struct UnitData {
-    pub inhabited: bool,
+// The per-cell state a rule works with. `Default` is the empty cell.
+pub trait CellState: Copy + PartialEq + Default + Send + Sync {
+    // Whether a state is exactly one of empty or alive, so that a bit per cell stores it.
+    const BINARY: bool = false;
+
+    fn alive() -> Self;
+
+    fn is_alive(&self) -> bool;
+
+    fn color(&self, is_penta: bool) -> [f32; 4];
+}
+
+impl CellState for bool {
+    const BINARY: bool = true;
+
+    fn alive() -> Self {
+        true
+    }
+
+    fn is_alive(&self) -> bool {
+        *self
+    }
+
+    fn color(&self, is_penta: bool) -> [f32; 4] {
+        if *self {
+            data::UNIT_COLOR
+        } else if is_penta {
+            data::ANOTHER_BACK_COLOR
+        } else {
+            data::BACK_COLOR
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct UnitData<S = bool> {
+    pub state: S,
     pub marked: bool,
 }
 
-impl UnitData {
+impl<S: CellState> UnitData<S> {
     pub fn new() -> Self {
         Self::empty()
     }
 
     pub fn empty() -> Self {
         Self {
-            inhabited: false,
+            state: S::default(),
             marked: false,
         }
     }
 
+    pub fn is_alive(&self) -> bool {
+        self.state.is_alive()
+    }
+
     pub fn with_added_life(mut self) -> Self {
-        self.inhabited = true;
+        self.state = S::alive();
         self
     }
 
     pub fn with_removed_life(mut self) -> Self {
-        self.inhabited = false;
+        self.state = S::default();
         self
     }
 
     pub fn add_life(&mut self) {
-        self.inhabited = true;
+        self.state = S::alive();
     }
 
     pub fn remove_life(&mut self) {
-        self.inhabited = false;
+        self.state = S::default();
+    }
+
+    pub fn with_set_life(self, inh: bool) -> Self {
+        if inh {
+            self.with_added_life()
+        } else {
+            self.with_removed_life()
+        }
     }
 
-    pub fn with_set_life(mut self, inh : bool) -> Self{
-        self.inhabited = inh;
+    pub fn with_state(mut self, state: S) -> Self {
+        self.state = state;
         self
     }
 
@@ -59,19 +105,13 @@ impl UnitData {
         self.marked = false;
     }
 
-    pub fn randomize_life(&mut self, p: f64) {
-        let inhabited = rand::random::<u32>() % 100 < ((100.0 * p.abs()) as u32);
-        self.inhabited = inhabited;
+    pub fn randomize_life<R: rand::Rng + ?Sized>(&mut self, p: f64, rng: &mut R) {
+        let inhabited = rng.gen_bool(p.abs().min(1.0));
+        *self = self.with_set_life(inhabited);
     }
 
     pub fn compute_color(&self, is_penta: bool) -> [f32; 4] {
-        let color = if self.inhabited {
-            data::UNIT_COLOR
-        } else if is_penta {
-            data::ANOTHER_BACK_COLOR
-        } else {
-            data::BACK_COLOR
-        };
+        let color = self.state.color(is_penta);
 
         if self.marked {
             let marked_color = [1.0, 0.0, 0.0, 0.9];
@@ -82,7 +122,7 @@ impl UnitData {
     }
 }
 
-impl Default for UnitData {
+impl<S: CellState> Default for UnitData<S> {
     fn default() -> Self {
         Self::new()
     }
@@ -99,3 +139,8 @@ fn merge_colors(lhs: &[f32; 4], rhs: &[f32; 4]) -> [f32; 4] {
         (a1 + a2) / 2.0,
     ]
 }
+
+// `t == 0.0` gives `from`, `t == 1.0` gives `to`.
+pub(crate) fn lerp_colors(from: &[f32; 4], to: &[f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| from[i] + (to[i] - from[i]) * t)
+}
@@ -41,7 +41,16 @@ pub fn main() {
         let (vtxes, colors) = game
             .indecies
             .iter()
-            .flat_map(|x| game.cell_to_colored_face_vtxes(x, camera.view_direction().into()))
+            .flat_map(|x| {
+                let faces = game.cell_to_colored_face_vtxes(x, camera.view_direction().into());
+                match gui_state.is_previewed(x) {
+                    true => faces
+                        .into_iter()
+                        .map(|(vtx, _)| (vtx, engine::data::PREVIEW_COLOR))
+                        .collect(),
+                    false => faces,
+                }
+            })
             .map(|((x, y, z), color)| {
                 (Vector3 { x, y, z }, {
                     let [r, g, b, a] = color.map(|x| {
@@ -85,6 +94,14 @@ pub fn main() {
                     (&position, &button),
                 )
             }
+            if let Event::MouseMotion {
+                position,
+                handled: false,
+                ..
+            } = event
+            {
+                gui_state.handle_mouse_motion(&model.geometry, &camera, &context, &game, position)
+            }
         });
 
         frame_input
@@ -98,8 +115,13 @@ pub fn main() {
         //update camera
 
         frame_input.events.iter().for_each(|event| {
-            if let Event::KeyPress { kind, .. } = event {
-                gui_state.handle_keyboard_event(&mut camera, *kind)
+            if let Event::KeyPress {
+                kind,
+                modifiers,
+                handled: false,
+            } = event
+            {
+                gui_state.handle_keyboard_event(&mut camera, &mut game, *kind, modifiers)
             }
         });
 
@@ -1,14 +1,34 @@
 use std::collections::VecDeque;
 
-use engine::game::as_number;
+use engine::{
+    edit::Edit,
+    game::as_number,
+    pattern::{IjPattern, Orientation, Pattern},
+    pattern_file::PatternFile,
+    rules::{PentagonPolicy, SimpleRules},
+};
 use three_d::{Camera, OrbitControl};
 
 pub struct GUIState {
     pub pause: bool,
     pub skip_frame: bool,
-    patterns: Vec<(&'static str, Box<dyn engine::pattern::Pattern>)>,
-    pub toggled_pattern: Option<&'static str>,
+    patterns: Vec<(String, Box<dyn Pattern>)>,
+    pub toggled_pattern: Option<String>,
+    captured: usize,
+    // The pattern file of the last capture, to be copied out of the text box.
+    exported: Option<String>,
+    capture_error: Option<String>,
+    orientation: Orientation,
+    // The cell under the cursor while a pattern is toggled, and where the
+    // pattern would land if it was clicked.
+    hovered: Option<h3o::CellIndex>,
+    preview: Vec<h3o::CellIndex>,
+    placement_error: Option<String>,
     pub rules: engine::rules::SimpleRules,
+    rule_text: String,
+    rule_error: Option<String>,
+    seed_text: String,
+    seed_error: Option<String>,
     pub orbit_control: OrbitControl,
     pub fps: VecDeque<f64>,
     pub time_beg: Option<f64>,
@@ -31,9 +51,23 @@ impl GUIState {
         Self {
             pause: false,
             skip_frame: false,
-            patterns: engine::pattern::create_pattern_map(),
+            patterns: engine::pattern::create_pattern_map()
+                .into_iter()
+                .map(|(name, pattern)| (name.to_string(), pattern))
+                .collect(),
             toggled_pattern: None,
+            captured: 0,
+            exported: None,
+            capture_error: None,
+            orientation: Orientation::default(),
+            hovered: None,
+            preview: Vec::new(),
+            placement_error: None,
             rules: engine::rules::SimpleRules::default(),
+            rule_text: engine::rules::SimpleRules::default().to_string(),
+            rule_error: None,
+            seed_text: String::new(),
+            seed_error: None,
             orbit_control: OrbitControl::new(*camera.target(), 1.0, 100.0),
             fps: VecDeque::new(),
             time_beg: None,
@@ -72,6 +106,47 @@ impl GUIState {
         self.pause = !self.pause;
     }
 
+    fn toggled(&self) -> Option<&dyn Pattern> {
+        let toggled_pattern = self.toggled_pattern.as_ref()?;
+        self.patterns
+            .iter()
+            .find(|(name, _pattern)| name == toggled_pattern)
+            .map(|(_name, pattern)| pattern.as_ref())
+    }
+
+    // Turns the live marked cells into a new pattern and toggles it.
+    fn capture_marked(&mut self, game: &engine::game::Game) {
+        match IjPattern::capture(&game.marked_life()) {
+            Ok(pattern) => {
+                self.captured += 1;
+                let name = format!("Captured {}", self.captured);
+                let file = PatternFile {
+                    name: Some(name.clone()),
+                    rule: Some(self.rules.to_string()),
+                    ..PatternFile::new(pattern)
+                };
+
+                self.exported = Some(file.to_string());
+                self.capture_error = None;
+                self.patterns.push((name.clone(), Box::new(file)));
+                self.toggled_pattern = Some(name);
+                self.update_preview();
+            }
+            Err(err) => self.capture_error = Some(err.to_string()),
+        }
+    }
+
+    fn update_preview(&mut self) {
+        self.preview = match (self.toggled(), self.hovered) {
+            (Some(pattern), Some(index)) => pattern.place(&index, self.orientation).cells,
+            _ => Vec::new(),
+        };
+    }
+
+    pub fn is_previewed(&self, index: &h3o::CellIndex) -> bool {
+        self.preview.contains(index)
+    }
+
     pub fn draw_ui(&mut self, gui_context: &three_d::egui::Context, game: &mut engine::game::Game) {
         use three_d::egui::*;
 
@@ -101,13 +176,38 @@ impl GUIState {
                         {
                             self.pause = !self.pause;
                         }
+                        if ui
+                            .add_enabled(game.history_len() > 0, Button::new("Back"))
+                            .clicked()
+                        {
+                            game.step_back(1);
+                            self.pause = true;
+                        }
                         if ui.add(Button::new("Clear")).clicked() {
-                            game.kill_everything();
+                            game.edit(Edit::Clear);
                         }
 
                         if ui.add(Button::new("Fill")).clicked() {
-                            game.spawn_life();
+                            game.reseed(game.seed().wrapping_add(1));
+                            game.edit(Edit::Fill);
                         }
+
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(game.can_undo(), Button::new("Undo"))
+                                .clicked()
+                            {
+                                game.undo();
+                            }
+                            if ui
+                                .add_enabled(game.can_redo(), Button::new("Redo"))
+                                .clicked()
+                            {
+                                game.redo();
+                            }
+                        });
+
+                        ui.add(Slider::new(&mut game.density, 0.0..=1.0).text("Density"));
                     });
 
                     ui.separator();
@@ -125,34 +225,98 @@ impl GUIState {
                     });
                 });
 
+                ui.label(format!("Generation: {}", game.generation()));
+
+                ui.horizontal(|ui| {
+                    ui.label("Seed:");
+                    let response =
+                        ui.add(TextEdit::singleline(&mut self.seed_text).desired_width(160.0));
+
+                    // Leaving the box without changing the seed keeps the world and its history.
+                    if response.lost_focus() {
+                        match self.seed_text.trim().parse() {
+                            Ok(seed) if seed == game.seed() => self.seed_error = None,
+                            Ok(seed) => {
+                                game.reseed(seed);
+                                game.edit(Edit::Fill);
+                                self.seed_error = None;
+                            }
+                            Err(_) => {
+                                self.seed_error =
+                                    Some(format!("'{}' is not a seed", self.seed_text))
+                            }
+                        }
+                    }
+                    if !response.has_focus() {
+                        self.seed_text = game.seed().to_string();
+                    }
+                });
+
+                if let Some(err) = &self.seed_error {
+                    ui.colored_label(Color32::RED, err);
+                }
+
                 ui.separator();
 
                 ui.heading("Patterns");
                 ui.label("Choose pattern:");
 
+                let toggled_before = self.toggled_pattern.clone();
                 self.patterns.iter().for_each(|(k, _build_pattern)| {
-                    let mut b = Button::new(k.to_string());
-                    if let Some(pattern_name) = self.toggled_pattern {
-                        if pattern_name == *k {
-                            b = b.fill(Color32::from_rgb(57, 115, 172))
-                        }
-                    };
+                    let toggled = self.toggled_pattern.as_ref() == Some(k);
+                    let mut b = Button::new(k.as_str());
+                    if toggled {
+                        b = b.fill(Color32::from_rgb(57, 115, 172))
+                    }
+
+                    if ui.add(b).clicked() {
+                        self.toggled_pattern = if toggled { None } else { Some(k.clone()) };
+                    }
+                });
 
-                    match (ui.add(b).clicked(), self.toggled_pattern) {
-                        (true, Some(toggled_pattern)) if toggled_pattern == *k => {
-                            self.toggled_pattern = None;
-                        }
-                        (true, _) => self.toggled_pattern = Some(*k),
-                        _ => (),
-                    };
+                if self.toggled_pattern != toggled_before {
+                    self.update_preview();
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("Orientation: {}", self.orientation));
+                    if ui.add(Button::new("Rotate")).clicked() {
+                        self.orientation = self.orientation.rotated();
+                        self.update_preview();
+                    }
+                    if ui.add(Button::new("Mirror")).clicked() {
+                        self.orientation = self.orientation.mirrored();
+                        self.update_preview();
+                    }
                 });
 
                 ui.label("Left-click to spawn");
+
+                if let Some(err) = &self.placement_error {
+                    ui.colored_label(Color32::RED, err);
+                }
+
+                if ui.add(Button::new("Capture marked")).clicked() {
+                    self.capture_marked(game);
+                }
+
+                if let Some(err) = &self.capture_error {
+                    ui.colored_label(Color32::RED, err);
+                }
+
+                if let Some(exported) = &self.exported {
+                    ui.label("Pattern file of the capture:");
+                    ui.add(TextEdit::multiline(&mut exported.as_str()).code_editor());
+                }
+
                 ui.label("");
                 ui.separator();
 
                 ui.label("* Use arrows or WASD to rotate the camera");
                 ui.label("* Use Enter or Space to pause/unpause");
+                ui.label("* Use Ctrl+Z and Ctrl+Y to undo and redo edits");
+                ui.label("* Use R and Shift+R to rotate a pattern, F to mirror it");
+                ui.label("* Mark cells and capture them to make a pattern of the live ones");
                 ui.label("* Left-click on a sphere to mark a cell");
                 ui.label("* Right-click on a sphere to kill a cell");
 
@@ -160,28 +324,75 @@ impl GUIState {
 
                 ui.heading("Rules");
 
-                ui.label("Survives");
                 ui.horizontal(|ui| {
-                    self.rules
-                        .survives
-                        .iter_mut()
-                        .enumerate()
-                        .for_each(|(i, v)| {
-                            ui.add(Checkbox::new(v, i.to_string()));
-                        });
+                    ui.label("Rule:");
+                    if ui
+                        .add(TextEdit::singleline(&mut self.rule_text).desired_width(80.0))
+                        .lost_focus()
+                    {
+                        match self.rule_text.parse() {
+                            Ok(rules) => {
+                                self.rules = SimpleRules {
+                                    pentagons: self.rules.pentagons,
+                                    ..rules
+                                };
+                                self.rule_error = None;
+                                game.touch_all();
+                            }
+                            Err(err) => self.rule_error = Some(err.to_string()),
+                        }
+                    }
                 });
 
+                if let Some(err) = &self.rule_error {
+                    ui.colored_label(Color32::RED, err);
+                }
+
+                let mut rules_changed = false;
+
+                ui.label("Survives");
+                rules_changed |= count_checkboxes(ui, &mut self.rules.survives, 7);
+
                 ui.label("Emerges");
+                rules_changed |= count_checkboxes(ui, &mut self.rules.emerges, 7);
+
+                ui.label("Pentagons");
                 ui.horizontal(|ui| {
-                    self.rules
-                        .emerges
-                        .iter_mut()
-                        .enumerate()
-                        .for_each(|(i, v)| {
-                            ui.add(Checkbox::new(v, i.to_string()));
-                        });
+                    let separate = PentagonPolicy::Separate {
+                        survives: self.rules.survives,
+                        emerges: self.rules.emerges,
+                    };
+
+                    [
+                        (PentagonPolicy::SameTable, "Same"),
+                        (separate, "Separate"),
+                        (PentagonPolicy::Rescale, "Rescale"),
+                    ]
+                    .into_iter()
+                    .for_each(|(policy, label)| {
+                        let selected = std::mem::discriminant(&self.rules.pentagons)
+                            == std::mem::discriminant(&policy);
+                        if ui.radio(selected, label).clicked() && !selected {
+                            self.rules.pentagons = policy;
+                            game.touch_all();
+                        }
+                    });
                 });
 
+                if let PentagonPolicy::Separate { survives, emerges } = &mut self.rules.pentagons {
+                    ui.label("Pentagon survives");
+                    rules_changed |= count_checkboxes(ui, survives, 6);
+
+                    ui.label("Pentagon emerges");
+                    rules_changed |= count_checkboxes(ui, emerges, 6);
+                }
+
+                if rules_changed {
+                    game.touch_all();
+                    self.rule_text = self.rules.to_string();
+                    self.rule_error = None;
+                }
+
                 ui.label(" ");
                 ui.label(" ");
             });
@@ -206,30 +417,30 @@ impl GUIState {
                 return;
             };
 
-            if let (three_d::MouseButton::Left, Some(toggled_pattern)) =
-                (button, self.toggled_pattern)
-            {
-                self.patterns
-                    .iter()
-                    .find(|(name, _pattern)| name == &toggled_pattern)
-                    .iter()
-                    .for_each(|(_name, u)| {
-                        u.as_cells(&index).iter().for_each(|index| {
-                            game.get_mut_unit(index)
-                                .into_iter()
-                                .for_each(|u| u.add_life());
-                        })
-                    });
-            } else if let (three_d::MouseButton::Left, None) = (button, self.toggled_pattern) {
-                game.get_mut_unit(&index)
-                    .into_iter()
-                    .for_each(|u| u.marked = !u.marked);
+            if let (three_d::MouseButton::Left, Some(pattern)) = (button, self.toggled()) {
+                // Near pentagons only part of the pattern fits, place that much
+                // and tell which part was left out.
+                let placement = pattern.place(&index, self.orientation);
+                game.edit(Edit::Stamp(placement.cells.clone()));
+                self.placement_error = placement.complete(index).err().map(|err| {
+                    log::info!("{:?}", err);
+                    err.to_string()
+                });
+            } else if let (three_d::MouseButton::Left, None) = (button, &self.toggled_pattern) {
+                if let Some(&unit) = game.get_unit(index) {
+                    let unit = if unit.marked {
+                        unit.with_removed_marked()
+                    } else {
+                        unit.with_mark()
+                    };
+                    game.edit(Edit::SetCell(index, unit));
+                }
 
                 log::info!("{:?}", index);
             } else if let three_d::MouseButton::Right = button {
-                game.get_mut_unit(&index)
-                    .into_iter()
-                    .for_each(|u| u.remove_life());
+                if let Some(&unit) = game.get_unit(index) {
+                    game.edit(Edit::SetCell(index, unit.with_removed_life()));
+                }
             }
 
             self.skip_frame = true;
@@ -238,16 +449,59 @@ impl GUIState {
         }
     }
 
+    pub fn handle_mouse_motion(
+        &mut self,
+        geometry: impl IntoIterator<Item = impl three_d::Geometry>,
+        camera: &three_d::Camera,
+        context: &three_d::Context,
+        game: &engine::game::Game,
+        position: &three_d::LogicalPoint,
+    ) {
+        if self.toggled_pattern.is_none() {
+            return;
+        }
+
+        self.hovered = three_d::renderer::pick(context, camera, position, geometry)
+            .and_then(|three_d::Vector3 { x, y, z }| {
+                engine::game::as_spherical(&(x as f64, y as f64, z as f64))
+            })
+            .map(|i| i.to_cell(game.resolution));
+        self.update_preview();
+    }
+
     pub fn handle_keyboard_event(
         &mut self,
         camera: &mut three_d::Camera,
+        game: &mut engine::game::Game,
         kind: three_d::renderer::control::Key,
+        modifiers: &three_d::renderer::control::Modifiers,
     ) {
         use three_d::renderer::control::Key;
 
         let speed = 0.5;
 
         match kind {
+            Key::Z if modifiers.command && modifiers.shift => {
+                game.redo();
+            }
+            Key::Z if modifiers.command => {
+                game.undo();
+            }
+            Key::Y if modifiers.command => {
+                game.redo();
+            }
+            Key::R if modifiers.shift => {
+                self.orientation = self.orientation.rotated_back();
+                self.update_preview();
+            }
+            Key::R => {
+                self.orientation = self.orientation.rotated();
+                self.update_preview();
+            }
+            Key::F => {
+                self.orientation = self.orientation.mirrored();
+                self.update_preview();
+            }
             Key::ArrowDown | Key::S => camera.rotate_around(
                 &three_d::Vector3 {
                     x: 0.0,
@@ -298,3 +552,19 @@ impl GUIState {
         self.skip_frame = false;
     }
 }
+
+// One checkbox per neighbour count below `len`, returns whether any of them changed.
+fn count_checkboxes(ui: &mut three_d::egui::Ui, table: &mut [bool; 7], len: usize) -> bool {
+    ui.horizontal(|ui| {
+        table
+            .iter_mut()
+            .take(len)
+            .enumerate()
+            .fold(false, |changed, (i, v)| {
+                ui.add(three_d::egui::Checkbox::new(v, i.to_string()))
+                    .changed()
+                    || changed
+            })
+    })
+    .inner
+}